@@ -1,9 +1,26 @@
+use super::Cli;
 use crate::commands;
 use crate::settings::global_user::GlobalUser;
 use crate::terminal::message::{Message, StdOut};
 use crate::terminal::{interactive, styles};
 
 use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum ConfigFile {
+    /// Check your configuration file and every environment in it for problems
+    Validate,
+    /// Print a JSON Schema for the configuration file, for use with editors and linters
+    Schema,
+    /// Rewrite deprecated keys in your configuration file into their current equivalents
+    Migrate {
+        /// Print the changes that would be made without writing them
+        #[structopt(long)]
+        dry_run: bool,
+    },
+}
 
 pub fn configure(api_key: bool, no_verify: bool) -> Result<()> {
     let user: GlobalUser = if !api_key {
@@ -45,3 +62,11 @@ pub fn configure(api_key: bool, no_verify: bool) -> Result<()> {
 
     commands::global_config(&user, !no_verify)
 }
+
+pub fn config_file(subcommand: ConfigFile, cli_params: &Cli) -> Result<()> {
+    match subcommand {
+        ConfigFile::Validate => commands::config::validate(&cli_params.config),
+        ConfigFile::Schema => commands::config::schema(),
+        ConfigFile::Migrate { dry_run } => commands::config::migrate(&cli_params.config, dry_run),
+    }
+}
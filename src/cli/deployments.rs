@@ -0,0 +1,37 @@
+use super::Cli;
+use crate::commands;
+use crate::settings::global_user::GlobalUser;
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum Deployments {
+    /// List the deployments published from this machine, which `wrangler rollback` can publish again
+    List,
+}
+
+pub fn deployments(deployments: Deployments, cli_params: &Cli) -> Result<()> {
+    log::info!("Getting project settings");
+    cli_params.with_manifests(|manifest| {
+        let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+
+        match &deployments {
+            Deployments::List => commands::deployments::list(&target),
+        }
+    })
+}
+
+pub fn rollback(to: Option<u32>, cli_params: &Cli) -> Result<()> {
+    log::info!("Getting User settings");
+    let user = GlobalUser::new()?;
+
+    log::info!("Getting project settings");
+    cli_params.with_manifests(|manifest| {
+        let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+        let deployments = manifest.get_deployments(cli_params.environment.as_deref())?;
+
+        commands::deployments::rollback(&user, &target, deployments, to)
+    })
+}
@@ -1,5 +1,5 @@
 use crate::commands;
-use crate::settings::toml::TargetType;
+use crate::settings::toml::{ConfigFormat, TargetType};
 
 use anyhow::Result;
 
@@ -8,6 +8,7 @@ pub fn generate(
     site: bool,
     template: Option<String>,
     target_type: Option<TargetType>,
+    format: ConfigFormat,
 ) -> Result<()> {
     const DEFAULT_TEMPLATE: &str = "https://github.com/cloudflare/worker-template";
     const RUST_TEMPLATE: &str = "https://github.com/cloudflare/rustwasm-worker-template";
@@ -29,5 +30,5 @@ pub fn generate(
         name
     );
 
-    commands::generate(&name, template, target_type, site)
+    commands::generate(&name, template, target_type, site, format)
 }
@@ -1,7 +1,15 @@
-use crate::{commands, settings::toml::TargetType};
+use crate::{
+    commands,
+    settings::toml::{ConfigFormat, TargetType},
+};
 use anyhow::Result;
 
-pub fn init(name: Option<String>, site: bool, target_type: Option<TargetType>) -> Result<()> {
+pub fn init(
+    name: Option<String>,
+    site: bool,
+    target_type: Option<TargetType>,
+    format: ConfigFormat,
+) -> Result<()> {
     let target_type = if site {
         // Workers Sites projects are always webpack for now
         Some(TargetType::Webpack)
@@ -9,5 +17,5 @@ pub fn init(name: Option<String>, site: bool, target_type: Option<TargetType>) -
         target_type
     };
 
-    commands::init(name.as_deref(), target_type, site)
+    commands::init(name.as_deref(), target_type, site, format)
 }
@@ -0,0 +1,56 @@
+use super::Cli;
+use super::{AdhocMigration, ClassMigration};
+use crate::commands;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum Migrations {
+    /// Show the Durable Object migrations the next publish would apply, without uploading anything
+    Plan {
+        #[structopt(flatten)]
+        migration: AdhocMigration,
+    },
+    /// Append a migration with the given class operations and the next tag to your configuration file
+    New {
+        #[structopt(flatten)]
+        classes: ClassMigration,
+
+        /// Use this tag instead of the next one in sequence
+        #[structopt(long)]
+        tag: Option<String>,
+    },
+}
+
+pub fn migrations(migrations: Migrations, cli_params: &Cli) -> Result<()> {
+    log::info!("Getting project settings");
+    let manifest = Manifest::new(&cli_params.config)?;
+    let mut target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+
+    match migrations {
+        Migrations::Plan { migration } => {
+            log::info!("Getting User settings");
+            let user = GlobalUser::new()?;
+
+            if let Some(migration) = migration.into_migrations() {
+                target.migrations = Some(migration);
+            }
+            commands::migrations::plan(&user, &target)
+        }
+        Migrations::New { classes, tag } => match classes.into_migration() {
+            Some(migration) => commands::migrations::new(
+                &cli_params.config,
+                &target,
+                cli_params.environment.as_deref(),
+                migration,
+                tag,
+            ),
+            None => anyhow::bail!(
+                "Specify at least one of --new-class, --delete-class, --rename-class or --transfer-class"
+            ),
+        },
+    }
+}
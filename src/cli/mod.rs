@@ -1,11 +1,13 @@
 pub mod build;
 pub mod config;
+pub mod deployments;
 pub mod dev;
 pub mod generate;
 pub mod init;
 pub mod kv;
 pub mod login;
 pub mod logout;
+pub mod migrations;
 pub mod preview;
 pub mod publish;
 pub mod r2;
@@ -17,7 +19,8 @@ pub mod whoami;
 
 pub mod exec {
     pub use super::build::build;
-    pub use super::config::configure;
+    pub use super::config::{config_file, configure};
+    pub use super::deployments::{deployments, rollback};
     pub use super::dev::dev;
     pub use super::generate::generate;
     pub use super::init::init;
@@ -26,6 +29,7 @@ pub mod exec {
     pub use super::kv::kv_namespace;
     pub use super::login::login;
     pub use super::logout::logout;
+    pub use super::migrations::migrations;
     pub use super::preview::preview;
     pub use super::publish::publish;
     pub use super::r2::r2_bucket;
@@ -36,6 +40,7 @@ pub mod exec {
     pub use super::whoami::whoami;
 }
 
+use std::env;
 use std::net::IpAddr;
 use std::path::PathBuf;
 use std::str::FromStr;
@@ -46,8 +51,12 @@ use crate::preview::HttpMethod;
 use crate::settings::toml::migrations::{
     DurableObjectsMigration, Migration, MigrationTag, Migrations, RenameClass, TransferClass,
 };
-use crate::settings::toml::TargetType;
+use crate::settings::toml::{
+    member_commands, ConfigFormat, Manifest, TargetType, Workspace, MEMBER_COMMANDS,
+};
+use crate::terminal::message::{Message, StdOut};
 
+use anyhow::Result;
 use clap::AppSettings;
 use structopt::StructOpt;
 use url::Url;
@@ -73,10 +82,69 @@ pub struct Cli {
     #[structopt(name = "env", long, short = "e", global = true)]
     pub environment: Option<String>,
 
+    /// Workspace member to perform a command on, by name or directory.
+    #[structopt(long, global = true)]
+    pub worker: Option<String>,
+
+    /// Perform a command on every workspace member.
+    #[structopt(long, global = true, conflicts_with = "worker")]
+    pub all: bool,
+
+    /// Times to retry API requests that fail with a transient error [default: $WRANGLER_MAX_RETRIES or 3]
+    #[structopt(long, global = true)]
+    pub max_retries: Option<u32>,
+
     #[structopt(subcommand)]
     pub command: Command,
 }
 
+impl Cli {
+    /// `--worker` and `--all` are global so that they can go anywhere on the
+    /// command line, but only commands that run on workspace members take them.
+    pub fn check_workspace_flags(&self) -> Result<()> {
+        let runs_on_members = MEMBER_COMMANDS.contains(&self.command.name());
+        anyhow::ensure!(
+            runs_on_members || (self.worker.is_none() && !self.all),
+            "--worker and --all can only be used with {}",
+            member_commands()
+        );
+        Ok(())
+    }
+
+    /// Runs `f` on the project's manifest or, for a workspace, on the manifest of
+    /// each member picked with `--worker` or `--all`, from inside that member's
+    /// directory. Stops at the first member that fails.
+    pub fn with_manifests(&self, mut f: impl FnMut(&Manifest) -> Result<()>) -> Result<()> {
+        let workspace = match Workspace::load(&self.config)? {
+            Some(workspace) => workspace,
+            None => {
+                anyhow::ensure!(
+                    self.worker.is_none() && !self.all,
+                    "--worker and --all can only be used with a workspace configuration file"
+                );
+                return f(&Manifest::new(&self.config)?);
+            }
+        };
+
+        let cwd = env::current_dir()?;
+        for member in workspace.select(self.worker.as_deref(), self.all)? {
+            if self.all {
+                StdOut::working(&format!(
+                    "{} ({})",
+                    member.manifest.name,
+                    member.dir.display()
+                ));
+            }
+            env::set_current_dir(&member.dir)?;
+            let result = f(&member.manifest);
+            env::set_current_dir(&cwd)?;
+            result?;
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, StructOpt)]
 pub enum Command {
     /// Interact with your Workers KV Namespaces
@@ -99,6 +167,14 @@ pub enum Command {
     #[structopt(name = "route", setting = AppSettings::SubcommandRequiredElseHelp)]
     Route(route::Route),
 
+    /// Inspect and manage Durable Object migrations
+    #[structopt(name = "migrations", setting = AppSettings::SubcommandRequiredElseHelp)]
+    Migrations(migrations::Migrations),
+
+    /// Inspect the deployments published from this machine
+    #[structopt(name = "deployments", setting = AppSettings::SubcommandRequiredElseHelp)]
+    Deployments(deployments::Deployments),
+
     /// Generate a secret that can be referenced in the worker script
     #[structopt(name = "secret", setting = AppSettings::SubcommandRequiredElseHelp)]
     Secret(secret::Secret),
@@ -120,6 +196,10 @@ pub enum Command {
         /// Initializes a Workers Sites project. Overrides 'type' and 'template'
         #[structopt(long, short = "s")]
         site: bool,
+
+        /// The format of the configuration file to write (toml, json or yaml)
+        #[structopt(long, default_value = "toml")]
+        format: ConfigFormat,
     },
 
     /// Create a wrangler.toml for an existing project
@@ -135,6 +215,10 @@ pub enum Command {
         /// Initializes a Workers Sites project. Overrides `type` and `template`
         #[structopt(long, short = "s")]
         site: bool,
+
+        /// The format of the configuration file to write (toml, json or yaml)
+        #[structopt(long, default_value = "toml")]
+        format: ConfigFormat,
     },
 
     /// Build your worker
@@ -209,6 +293,31 @@ pub enum Command {
 
         #[structopt(flatten)]
         migration: AdhocMigration,
+
+        /// Build your worker and its upload form without publishing anything
+        #[structopt(long)]
+        dry_run: bool,
+
+        /// With --dry-run, write each part of the upload form to this directory
+        #[structopt(long, requires = "dry-run", parse(from_os_str))]
+        outdir: Option<PathBuf>,
+
+        /// Show what publishing changes on the deployed worker, and confirm before publishing
+        #[structopt(long, conflicts_with = "dry-run")]
+        diff: bool,
+
+        /// Delete routes that point to your worker but are no longer in your configuration file.
+        /// Only zones your configuration file still has routes in are pruned
+        #[structopt(long, conflicts_with = "dry-run")]
+        prune_routes: bool,
+
+        /// Publish the top level of your configuration file and then every environment, stopping at the first failure
+        #[structopt(long, conflicts_with_all = &["dry-run", "envs"])]
+        all_envs: bool,
+
+        /// Publish these environments in order, stopping at the first failure
+        #[structopt(long, use_delimiter = true, conflicts_with = "dry-run")]
+        envs: Vec<String>,
     },
 
     /// Authenticate Wrangler with a Cloudflare API Token or Global API Key
@@ -221,6 +330,17 @@ pub enum Command {
         /// Do not verify provided credentials before writing out Wrangler config file
         #[structopt(name = "no-verify", long)]
         no_verify: bool,
+
+        #[structopt(subcommand)]
+        subcommand: Option<config::ConfigFile>,
+    },
+
+    /// Publish a previous deployment of your worker again
+    #[structopt(name = "rollback")]
+    Rollback {
+        /// The deployment to roll back to (find using `wrangler deployments list`); defaults to the one before the current deployment
+        #[structopt(long)]
+        to: Option<u32>,
     },
 
     /// Configure your workers.dev subdomain
@@ -305,8 +425,70 @@ pub enum Command {
     Logout,
 }
 
+impl Command {
+    /// The command's name, as typed on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::KvNamespace(_) => "kv:namespace",
+            Command::KvKey(_) => "kv:key",
+            Command::KvBulk(_) => "kv:bulk",
+            Command::R2(_) => "r2",
+            Command::Route(_) => "route",
+            Command::Migrations(_) => "migrations",
+            Command::Deployments(_) => "deployments",
+            Command::Secret(_) => "secret",
+            Command::Generate { .. } => "generate",
+            Command::Init { .. } => "init",
+            Command::Build => "build",
+            Command::Preview { .. } => "preview",
+            Command::Dev { .. } => "dev",
+            Command::Publish { .. } => "publish",
+            Command::Config { .. } => "config",
+            Command::Rollback { .. } => "rollback",
+            Command::Subdomain { .. } => "subdomain",
+            Command::Whoami => "whoami",
+            Command::Tail { .. } => "tail",
+            Command::Login { .. } => "login",
+            Command::Logout => "logout",
+        }
+    }
+}
+
 #[derive(Debug, Clone, StructOpt)]
 pub struct AdhocMigration {
+    #[structopt(flatten)]
+    classes: ClassMigration,
+
+    /// Specify the existing migration tag for the script.
+    #[structopt(name = "old-tag", long)]
+    old_tag: Option<String>,
+
+    /// Specify the new migration tag for the script
+    #[structopt(name = "new-tag", long)]
+    new_tag: Option<String>,
+}
+
+impl AdhocMigration {
+    pub fn into_migrations(self) -> Option<Migrations> {
+        let migration = self.classes.into_migration();
+
+        if migration.is_some() || self.old_tag.is_some() || self.new_tag.is_some() {
+            Some(Migrations::Adhoc {
+                script_tag: MigrationTag::Unknown,
+                provided_old_tag: self.old_tag,
+                new_tag: self.new_tag,
+                migration: migration.map(|durable_objects| Migration { durable_objects }),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// The Durable Object class operations shared by ad-hoc migrations on
+/// `wrangler publish` and `wrangler migrations new`.
+#[derive(Debug, Clone, StructOpt)]
+pub struct ClassMigration {
     /// Allow durable objects to be created from a class in your script
     #[structopt(name = "new-class", long, number_of_values = 1)]
     new_class: Vec<String>,
@@ -323,18 +505,10 @@ pub struct AdhocMigration {
     /// this script
     #[structopt(name = "transfer-class", long, number_of_values = 3, value_names(&["from script", "from class", "to class"]))]
     transfer_class: Vec<String>,
-
-    /// Specify the existing migration tag for the script.
-    #[structopt(name = "old-tag", long)]
-    old_tag: Option<String>,
-
-    /// Specify the new migration tag for the script
-    #[structopt(name = "new-tag", long)]
-    new_tag: Option<String>,
 }
 
-impl AdhocMigration {
-    pub fn into_migrations(self) -> Option<Migrations> {
+impl ClassMigration {
+    pub fn into_migration(self) -> Option<DurableObjectsMigration> {
         let migration = DurableObjectsMigration {
             new_classes: self.new_class,
             deleted_classes: self.delete_class,
@@ -374,23 +548,10 @@ impl AdhocMigration {
             && migration.renamed_classes.is_empty()
             && migration.transferred_classes.is_empty();
 
-        if !is_migration_empty || self.old_tag.is_some() || self.new_tag.is_some() {
-            let migration = if !is_migration_empty {
-                Some(Migration {
-                    durable_objects: migration,
-                })
-            } else {
-                None
-            };
-
-            Some(Migrations::Adhoc {
-                script_tag: MigrationTag::Unknown,
-                provided_old_tag: self.old_tag,
-                new_tag: self.new_tag,
-                migration,
-            })
-        } else {
+        if is_migration_empty {
             None
+        } else {
+            Some(migration)
         }
     }
 }
@@ -424,6 +585,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn workspace_flags_checking() {
+        let publish = Cli::from_iter(&["wrangler", "publish", "--worker", "api"]);
+        assert!(publish.check_workspace_flags().is_ok());
+
+        let dev = Cli::from_iter(&["wrangler", "dev", "--worker", "api"]);
+        assert!(dev.check_workspace_flags().is_err());
+
+        let routes = Cli::from_iter(&["wrangler", "route", "list", "--all"]);
+        assert!(routes.check_workspace_flags().is_err());
+
+        // every command named in messages is one the flags are accepted by
+        let rollback = Cli::from_iter(&["wrangler", "rollback", "--all"]);
+        assert!(rollback.check_workspace_flags().is_ok());
+        assert_eq!(rollback.command.name(), "rollback");
+        assert!(member_commands().ends_with("deployments and rollback"));
+    }
+
     #[test]
     fn adhoc_migration_parsing() {
         let command = Cli::from_iter(&[
@@ -479,4 +658,22 @@ mod tests {
             assert!(false, "Unkown command {:?}", command)
         }
     }
+
+    #[test]
+    fn envs_parsing() {
+        let command =
+            Cli::from_iter(&["wrangler", "publish", "--envs", "staging,production"]).command;
+
+        if let Command::Publish { envs, all_envs, .. } = command {
+            assert_eq!(envs, vec!["staging", "production"]);
+            assert!(!all_envs);
+        } else {
+            assert!(false, "Unkown command {:?}", command)
+        }
+
+        assert!(
+            Cli::from_iter_safe(&["wrangler", "publish", "--all-envs", "--envs", "staging"])
+                .is_err()
+        );
+    }
 }
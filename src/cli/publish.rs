@@ -1,20 +1,101 @@
 use super::AdhocMigration;
 use super::Cli;
 use crate::commands;
-use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::commands::publish::PublishOutput;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Manifest;
 use crate::terminal::message::{Message, Output, StdOut};
 use crate::terminal::styles;
 
+use std::env;
+use std::iter;
+use std::path::PathBuf;
+
 use anyhow::Result;
+use once_cell::sync::OnceCell;
+
+/// Which of its modes `wrangler publish` runs in, from its mutually exclusive flags.
+#[derive(Debug, Clone)]
+pub enum PublishMode {
+    /// Publish the environment picked with `--env`
+    Target,
+    /// Build the upload form without publishing it, writing it to `outdir` if given
+    DryRun { outdir: Option<PathBuf> },
+    /// Publish the top level and then every environment
+    AllEnvironments,
+    /// Publish these environments in order
+    Environments(Vec<String>),
+}
+
+impl PublishMode {
+    pub fn new(dry_run: bool, outdir: Option<PathBuf>, all_envs: bool, envs: Vec<String>) -> Self {
+        if dry_run {
+            PublishMode::DryRun { outdir }
+        } else if all_envs {
+            PublishMode::AllEnvironments
+        } else if !envs.is_empty() {
+            PublishMode::Environments(envs)
+        } else {
+            PublishMode::Target
+        }
+    }
+}
 
 pub fn publish(
     release: bool,
     output: Option<String>,
     migration: AdhocMigration,
+    mode: PublishMode,
+    options: commands::PublishOptions,
     cli_params: &Cli,
 ) -> Result<()> {
-    log::info!("Getting User settings");
-    let user = GlobalUser::new()?;
+    let json = output.as_deref() == Some("json");
+    let mut report = FailureReport::default();
+
+    let result = publish_manifests(
+        release,
+        output,
+        migration,
+        mode,
+        options,
+        cli_params,
+        &mut report,
+    );
+    // failures are reported on stdout too, so JSON output is always complete
+    if let Err(e) = &result {
+        if json && !report.reported {
+            StdOut::as_json(&PublishOutput::failed(report.name, e));
+        }
+    }
+    result
+}
+
+// What reporting a failure as JSON needs to know
+#[derive(Default)]
+struct FailureReport {
+    /// The name of the script being published, once it's known
+    name: String,
+    /// Whether the failure was already reported in the summary of several environments
+    reported: bool,
+}
+
+fn publish_manifests(
+    release: bool,
+    output: Option<String>,
+    migration: AdhocMigration,
+    mode: PublishMode,
+    options: commands::PublishOptions,
+    cli_params: &Cli,
+    report: &mut FailureReport,
+) -> Result<()> {
+    let several_envs = matches!(
+        mode,
+        PublishMode::AllEnvironments | PublishMode::Environments(_)
+    );
+    anyhow::ensure!(
+        !several_envs || cli_params.environment.is_none(),
+        "--all-envs and --envs choose the environments to publish; they can't be used with --env"
+    );
 
     if release {
         StdOut::warn(&format!(concat!(
@@ -26,21 +107,139 @@ pub fn publish(
         ));
     }
 
-    log::info!("Getting project settings");
-    let manifest = Manifest::new(&cli_params.config)?;
-    manifest.warn_about_compatibility_date();
-
-    let mut target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+    // commands on workspace members run from the member's directory
+    let mode = match mode {
+        PublishMode::DryRun {
+            outdir: Some(outdir),
+        } => PublishMode::DryRun {
+            outdir: Some(env::current_dir()?.join(outdir)),
+        },
+        mode => mode,
+    };
 
-    if let Some(migration) = migration.into_migrations() {
-        target.migrations = Some(migration);
-    }
+    let migration = migration.into_migrations();
+    // migration tags belong to a single script
+    anyhow::ensure!(
+        migration.is_none() || !cli_params.all,
+        "Migrations passed on the command line can't be applied to every worker; pick one with --worker"
+    );
+    anyhow::ensure!(
+        migration.is_none() || !several_envs,
+        "Migrations passed on the command line can't be applied to several environments; pick one with --env"
+    );
 
     let output = if output.as_deref() == Some("json") {
         Output::Json
     } else {
         Output::PlainText
     };
-    let deploy_config = manifest.get_deployments(cli_params.environment.as_deref())?;
-    commands::publish(&user, &mut target, deploy_config, output)
+
+    // a dry run only needs credentials for remote lookups, which it loads itself
+    let user = OnceCell::new();
+    let load_user = || {
+        user.get_or_try_init(|| {
+            log::info!("Getting User settings");
+            GlobalUser::new()
+        })
+    };
+
+    log::info!("Getting project settings");
+    cli_params.with_manifests(|manifest| {
+        report.name = manifest.name.clone();
+        manifest.warn_about_compatibility_date();
+
+        let environments = match &mode {
+            PublishMode::AllEnvironments => Some(all_environments(manifest)),
+            PublishMode::Environments(names) => Some(names.iter().cloned().map(Some).collect()),
+            PublishMode::Target | PublishMode::DryRun { .. } => None,
+        };
+        if let Some(environments) = environments {
+            report.reported = true;
+            return commands::publish::publish_environments(
+                load_user()?,
+                manifest,
+                &environments,
+                output,
+                options,
+            );
+        }
+
+        let mut target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+        report.name = target.name.clone();
+
+        if let Some(migration) = &migration {
+            target.migrations = Some(migration.clone());
+        }
+
+        if let PublishMode::DryRun { outdir } = &mode {
+            // each workspace member gets its own directory
+            let outdir = match outdir {
+                Some(outdir) if cli_params.all => Some(outdir.join(&target.name)),
+                _ => outdir.clone(),
+            };
+            return commands::publish::dry_run(&mut target, outdir.as_deref(), output);
+        }
+
+        let user = load_user()?;
+        let deploy_config = manifest.get_deployments(cli_params.environment.as_deref())?;
+        if let Some(published) =
+            commands::publish(user, &mut target, deploy_config, output, options)?
+        {
+            if output == Output::Json {
+                StdOut::as_json(&published);
+            }
+        }
+        Ok(())
+    })
+}
+
+// the top level first, then each environment by name
+fn all_environments(manifest: &Manifest) -> Vec<Option<String>> {
+    let mut names: Vec<String> = manifest
+        .env
+        .iter()
+        .flat_map(|env| env.keys().cloned())
+        .collect();
+    names.sort();
+
+    iter::once(None)
+        .chain(names.into_iter().map(Some))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::str::FromStr;
+
+    #[test]
+    fn it_lists_the_top_level_and_then_environments_by_name() {
+        let manifest = Manifest::from_str(
+            r#"
+name = "worker"
+type = "javascript"
+
+[env.staging]
+name = "worker-staging"
+
+[env.production]
+name = "worker-production"
+
+[env.canary]
+name = "worker-canary"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            all_environments(&manifest),
+            vec![
+                None,
+                Some("canary".to_string()),
+                Some("production".to_string()),
+                Some("staging".to_string()),
+            ]
+        );
+    }
 }
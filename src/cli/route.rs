@@ -1,5 +1,6 @@
 use super::Cli;
 use crate::commands;
+use crate::deploy::{DeployTarget, Zone};
 use crate::settings::{global_user::GlobalUser, toml::Manifest};
 
 use anyhow::Result;
@@ -21,19 +22,39 @@ pub enum Route {
 pub fn route(route: Route, cli_params: &Cli) -> Result<()> {
     let user = GlobalUser::new()?;
     let manifest = Manifest::new(&cli_params.config)?;
+    let zone_ids = zones(&manifest, cli_params.environment.as_deref())?
+        .iter()
+        .map(|zone| zone.id(&user))
+        .collect::<Result<Vec<_>>>()?;
+
+    match route {
+        Route::List => commands::route::list(&zone_ids, &user),
+        Route::Delete { route_id } => commands::route::delete(&zone_ids, &user, &route_id),
+    }
+}
+
+// the configuration's `zone_id`, or else every zone its routes name
+fn zones(manifest: &Manifest, environment: Option<&str>) -> Result<Vec<Zone>> {
     let zone_id = manifest
-        .get_environment(cli_params.environment.as_deref())?
+        .get_environment(environment)?
         .and_then(|e| e.zone_id.as_ref())
         .or_else(|| manifest.zone_id.as_ref());
+    if let Some(zone_id) = zone_id {
+        return Ok(vec![Zone::Id(zone_id.clone())]);
+    }
 
-    let zone_id = zone_id.ok_or_else(|| {
-        anyhow::anyhow!(
-        "You must specify a zone_id in your configuration file to use `wrangler route` commands."
-    )
-    })?;
-
-    match route {
-        Route::List => commands::route::list(zone_id, &user),
-        Route::Delete { route_id } => commands::route::delete(zone_id, &user, &route_id),
+    let mut zones = Vec::new();
+    for target in manifest.get_deployments(environment)? {
+        if let DeployTarget::Zoned(zoned) = target {
+            if !zones.contains(&zoned.zone) {
+                zones.push(zoned.zone);
+            }
+        }
     }
+
+    anyhow::ensure!(
+        !zones.is_empty(),
+        "You must specify a zone_id, or routes with a `zone_id` or `zone_name`, in your configuration file to use `wrangler route` commands."
+    );
+    Ok(zones)
 }
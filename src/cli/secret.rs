@@ -1,6 +1,6 @@
 use super::Cli;
 use crate::commands;
-use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::settings::global_user::GlobalUser;
 
 use anyhow::Result;
 use structopt::StructOpt;
@@ -26,11 +26,12 @@ pub fn secret(secret: Secret, cli_params: &Cli) -> Result<()> {
     let user = GlobalUser::new()?;
 
     log::info!("Getting project settings");
-    let manifest = Manifest::new(&cli_params.config)?;
-    let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
-    match secret {
-        Secret::Put { name } => commands::secret::create_secret(&name, &user, &target),
-        Secret::Delete { name } => commands::secret::delete_secret(&name, &user, &target),
-        Secret::List => commands::secret::list_secrets(&user, &target),
-    }
+    cli_params.with_manifests(|manifest| {
+        let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+        match &secret {
+            Secret::Put { name } => commands::secret::create_secret(name, &user, &target),
+            Secret::Delete { name } => commands::secret::delete_secret(name, &user, &target),
+            Secret::List => commands::secret::list_secrets(&user, &target),
+        }
+    })
 }
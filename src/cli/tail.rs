@@ -2,7 +2,7 @@ use super::Cli;
 use crate::commands;
 use crate::commands::tail::filter::*;
 use crate::commands::tail::websocket::{TailFormat, TailOptions};
-use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::settings::global_user::GlobalUser;
 
 use anyhow::Result;
 use url::Url;
@@ -21,11 +21,20 @@ pub fn tail(
     search: Option<String>,
     cli_params: &Cli,
 ) -> Result<()> {
+    anyhow::ensure!(
+        !cli_params.all,
+        "`wrangler tail` follows one worker at a time; pick one with --worker"
+    );
+
     let user = GlobalUser::new()?;
 
     // FIXME: If `name` is defined, allow the command to be run outside a `wrangler.toml` directory.
-    let manifest = Manifest::new(&cli_params.config)?;
-    let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+    let mut target = None;
+    cli_params.with_manifests(|manifest| {
+        target = Some(manifest.get_target(cli_params.environment.as_deref(), false)?);
+        Ok(())
+    })?;
+    let target = target.expect("a single manifest should have been selected");
     let account_id = target.account_id.load()?.to_string();
     let script_name = name.unwrap_or(target.name);
 
@@ -0,0 +1,269 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use toml_edit::{value, Document, Item, Table};
+
+use crate::settings::toml::ConfigFormat;
+use crate::terminal::message::{Message, StdOut};
+
+const BUILD_DIR: &str = "dist";
+const MODULE_MAIN: &str = "./worker.mjs";
+
+/// Rewrite deprecated constructs in a wrangler.toml into their current
+/// equivalents, printing a diff of the changes.
+pub fn migrate(config_path: &Path, dry_run: bool) -> Result<()> {
+    anyhow::ensure!(config_path.exists(), "{} not found", config_path.display());
+    anyhow::ensure!(
+        ConfigFormat::from_path(config_path) == ConfigFormat::Toml,
+        "`wrangler config migrate` only supports wrangler.toml files"
+    );
+
+    let source = fs::read_to_string(config_path)?;
+    let Upgrade { config, notes } = upgrade(&source)?;
+
+    if config == source {
+        for note in &notes {
+            StdOut::warn(note);
+        }
+        StdOut::success(&format!("{} is already up to date", config_path.display()));
+        return Ok(());
+    }
+
+    let path = config_path.display().to_string();
+    print!("{}", diff(&source, &config, &path));
+    for note in &notes {
+        StdOut::warn(note);
+    }
+
+    if dry_run {
+        StdOut::info("This was a dry run; no changes were written");
+    } else {
+        fs::write(config_path, config)?;
+        StdOut::success(&format!("Updated {}", path));
+    }
+
+    Ok(())
+}
+
+struct Upgrade {
+    config: String,
+    /// Things the user needs to check or do by hand
+    notes: Vec<String>,
+}
+
+fn upgrade(source: &str) -> Result<Upgrade> {
+    // toml_edit preserves comments and formatting for everything we don't touch
+    let mut doc = source
+        .parse::<Document>()
+        .map_err(|err| anyhow!("toml_edit failed to parse configuration file. {}", err))?;
+    let mut notes = Vec::new();
+
+    let top_level = doc.as_table_mut();
+    let webpack = top_level.get("type").and_then(Item::as_str) == Some("webpack");
+    if webpack {
+        *top_level.entry("type") = value("javascript");
+    }
+    let top_level_zone_id = has_key(top_level, "zone_id");
+    upgrade_scope(top_level, "", webpack, top_level_zone_id, &mut notes);
+
+    let env_names: Vec<String> = match top_level.get("env").and_then(Item::as_table) {
+        Some(envs) => envs.iter().map(|(name, _)| name.to_string()).collect(),
+        None => Vec::new(),
+    };
+    for name in env_names {
+        if let Some(env) = top_level.entry("env").as_table_mut() {
+            if let Some(env) = env.entry(&name).as_table_mut() {
+                let path = format!("env.{}.", name);
+                upgrade_scope(env, &path, webpack, top_level_zone_id, &mut notes);
+            }
+        }
+    }
+
+    Ok(Upgrade {
+        config: doc.to_string_in_original_order(),
+        notes,
+    })
+}
+
+fn upgrade_scope(
+    scope: &mut Table,
+    path: &str,
+    webpack: bool,
+    top_level_zone_id: bool,
+    notes: &mut Vec<String>,
+) {
+    // `kv-namespaces` has been renamed to `kv_namespaces`
+    if let Some(namespaces) = scope.remove("kv-namespaces") {
+        if has_key(scope, "kv_namespaces") {
+            notes.push(format!(
+                "Both `{0}kv-namespaces` and `{0}kv_namespaces` are set; kept `{0}kv_namespaces`",
+                path
+            ));
+        } else {
+            *scope.entry("kv_namespaces") = namespaces;
+        }
+    }
+
+    // `[durable_objects] bindings` has been renamed to `classes`
+    if has_key(scope, "durable_objects") {
+        if let Some(durable_objects) = scope.entry("durable_objects").as_table_mut() {
+            if let Some(bindings) = durable_objects.remove("bindings") {
+                if has_key(durable_objects, "classes") {
+                    notes.push(format!(
+                        "Both `{0}durable_objects.bindings` and `{0}durable_objects.classes` are set; kept `{0}durable_objects.classes`",
+                        path
+                    ));
+                } else {
+                    *durable_objects.entry("classes") = bindings;
+                }
+            }
+        }
+    }
+
+    // webpack projects are now javascript projects with a custom build
+    let webpack_config = scope.remove("webpack_config");
+    if webpack && (webpack_config.is_some() || path.is_empty()) && !has_key(scope, "build") {
+        let command = match webpack_config.as_ref().and_then(Item::as_str) {
+            Some(config) => format!("npx webpack --config {}", config),
+            None => "npx webpack".to_string(),
+        };
+        let build = scope.entry("build");
+        build["command"] = value(command);
+        build["upload"]["format"] = value("modules");
+        build["upload"]["dir"] = value(BUILD_DIR);
+        build["upload"]["main"] = value(MODULE_MAIN);
+        notes.push(format!(
+            "`{}build` runs webpack and uploads {}/{} as an ES module; check that your webpack configuration outputs a module there",
+            path,
+            BUILD_DIR,
+            MODULE_MAIN.trim_start_matches("./")
+        ));
+    } else if webpack_config.is_some() {
+        notes.push(format!(
+            "Removed `{}webpack_config`, which is only used by webpack projects",
+            path
+        ));
+    }
+
+    // routes can't be published without a zone, and there's no way to guess one
+    if (has_key(scope, "route") || has_key(scope, "routes"))
+        && !has_key(scope, "zone_id")
+        && !top_level_zone_id
+    {
+        notes.push(format!(
+            "`{0}routes` have no `zone_id`; add `{0}zone_id` by hand",
+            path
+        ));
+    }
+}
+
+fn has_key(table: &Table, key: &str) -> bool {
+    table.get(key).map_or(false, |item| !item.is_none())
+}
+
+fn diff(before: &str, after: &str, path: &str) -> String {
+    let before: Vec<&str> = before.split_inclusive('\n').collect();
+    let after: Vec<&str> = after.split_inclusive('\n').collect();
+
+    difflib::unified_diff(&before, &after, path, path, "", "", 3).concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::settings::toml::Manifest;
+    use std::str::FromStr;
+
+    const LEGACY: &str = r#"# my worker
+name = "worker"
+type = "webpack"
+account_id = "fakeaccountid"
+webpack_config = "webpack.prod.js" # production build
+zone_id = "samplezoneid"
+route = "example.com/*"
+
+# caches
+[[kv-namespaces]]
+binding = "CACHE"
+id = "somecrazylongidentifierstring"
+
+[durable_objects]
+bindings = [
+  { name = "COUNTER", class_name = "Counter" },
+]
+
+[env.staging]
+name = "staging-worker"
+webpack_config = "webpack.staging.js"
+kv-namespaces = [
+  { binding = "CACHE", id = "anotherwaytoolongidstring" },
+]
+"#;
+
+    #[test]
+    fn it_rewrites_deprecated_keys() {
+        let Upgrade { config, .. } = upgrade(LEGACY).unwrap();
+
+        assert!(!config.contains("type = \"webpack\""), "{}", config);
+        assert!(!config.contains("webpack_config"), "{}", config);
+        assert!(!config.contains("kv-namespaces"), "{}", config);
+        assert!(!config.contains("bindings"), "{}", config);
+
+        let manifest = Manifest::from_str(&config).unwrap();
+        assert_eq!(manifest.target_type.to_string(), "javascript");
+        assert_eq!(
+            manifest.build.unwrap().command,
+            Some("npx webpack --config webpack.prod.js".to_string())
+        );
+        assert_eq!(manifest.kv_namespaces.unwrap()[0].binding, "CACHE");
+        assert_eq!(
+            manifest.durable_objects.unwrap().classes.unwrap()[0].binding,
+            "COUNTER"
+        );
+
+        let staging = &manifest.env.unwrap()["staging"];
+        assert_eq!(
+            staging.build.as_ref().unwrap().command,
+            Some("npx webpack --config webpack.staging.js".to_string())
+        );
+        assert_eq!(staging.kv_namespaces.as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn it_keeps_comments() {
+        let Upgrade { config, .. } = upgrade(LEGACY).unwrap();
+
+        assert!(config.starts_with("# my worker\n"), "{}", config);
+        assert!(config.contains("# caches"), "{}", config);
+    }
+
+    #[test]
+    fn it_leaves_current_configs_alone() {
+        let current = "name = \"worker\"\ntype = \"javascript\"\nworkers_dev = true\n\n[[kv_namespaces]]\nbinding = \"CACHE\"\nid = \"somecrazylongidentifierstring\"\n";
+
+        let Upgrade { config, notes } = upgrade(current).unwrap();
+
+        assert_eq!(config, current);
+        assert!(notes.is_empty());
+    }
+
+    #[test]
+    fn it_notes_routes_without_a_zone() {
+        let source = "name = \"worker\"\ntype = \"javascript\"\nroutes = [\"example.com/*\"]\n";
+
+        let Upgrade { notes, .. } = upgrade(source).unwrap();
+
+        assert_eq!(notes.len(), 1);
+        assert!(notes[0].contains("zone_id"));
+    }
+
+    #[test]
+    fn it_diffs_changed_lines() {
+        let diff = diff("a\nb\nc\n", "a\nB\nc\n", "wrangler.toml");
+
+        assert!(diff.contains("-b\n"), "{}", diff);
+        assert!(diff.contains("+B\n"), "{}", diff);
+    }
+}
@@ -1,3 +1,7 @@
+mod migrate;
+mod schema;
+mod validate;
+
 #[cfg(not(target_os = "windows"))]
 use std::fs::File;
 #[cfg(not(target_os = "windows"))]
@@ -15,6 +19,10 @@ use crate::settings::{get_global_config_path, global_user::GlobalUser};
 use crate::terminal::message::{Message, StdOut};
 use crate::terminal::styles;
 
+pub use migrate::migrate;
+pub use schema::schema;
+pub use validate::validate;
+
 // set the permissions on the dir, we want to avoid that other user reads to file
 #[cfg(not(target_os = "windows"))]
 pub fn set_file_mode(file: &Path) {
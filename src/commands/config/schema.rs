@@ -0,0 +1,8 @@
+use anyhow::Result;
+
+use crate::settings::toml::schema::schema as config_schema;
+
+pub fn schema() -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(&config_schema())?);
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::settings::toml::diagnostics::{self, Severity};
+use crate::settings::toml::{ConfigFormat, Manifest};
+use crate::terminal::message::{Message, StdOut};
+
+pub fn validate(config_path: &Path) -> Result<()> {
+    anyhow::ensure!(config_path.exists(), "{} not found", config_path.display());
+
+    // diagnostics with positions are only available for TOML
+    if ConfigFormat::from_path(config_path) != ConfigFormat::Toml {
+        Manifest::new(config_path)?;
+        StdOut::success(&format!("{} is valid", config_path.display()));
+        return Ok(());
+    }
+
+    let source = fs::read_to_string(config_path)?;
+
+    let diagnostics = diagnostics::check_file(config_path, &source);
+    for diagnostic in &diagnostics {
+        println!("{}:{}", config_path.display(), diagnostic);
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+
+    if errors > 0 {
+        anyhow::bail!(
+            "{} has {} error(s) and {} warning(s)",
+            config_path.display(),
+            errors,
+            warnings
+        )
+    }
+
+    if warnings > 0 {
+        StdOut::warn(&format!(
+            "{} is valid, with {} warning(s)",
+            config_path.display(),
+            warnings
+        ));
+    } else {
+        StdOut::success(&format!("{} is valid", config_path.display()));
+    }
+
+    Ok(())
+}
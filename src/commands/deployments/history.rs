@@ -0,0 +1,220 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr};
+use crate::upload::form::{FormPart, PartContent};
+
+/// How many deployments are kept per script; older ones are removed.
+const MAX_DEPLOYMENTS: usize = 10;
+
+const DEPLOYMENT_FILE: &str = "deployment.json";
+
+/// An upload form published from this machine, kept so that it can be
+/// published again.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Deployment {
+    pub id: u32,
+    /// RFC 3339, in UTC
+    pub published_on: String,
+    /// The commit checked out when publishing, if publishing from a git repository
+    pub git_sha: Option<String>,
+    /// The deployment this one published again, if it was a rollback
+    pub rollback_of: Option<u32>,
+    pub parts: Vec<StoredPart>,
+}
+
+/// A part of a stored upload form. Its content is kept in a file next to the
+/// deployment.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct StoredPart {
+    pub name: String,
+    pub file_name: Option<String>,
+    pub content_type: Option<String>,
+    /// Relative to the deployment's directory
+    pub path: PathBuf,
+}
+
+// Deployments are kept per script, so each environment has its own history.
+// `home` is the Wrangler home directory, from `get_wrangler_home_dir`.
+fn history_dir(home: &Path, target: &Target) -> Result<PathBuf> {
+    Ok(home
+        .join("deployments")
+        .join(target.account_id.load()?)
+        .join(&target.name))
+}
+
+/// The script's deployments recorded on this machine, oldest first.
+pub fn load(home: &Path, target: &Target) -> Result<Vec<Deployment>> {
+    let dir = history_dir(home, target)?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut deployments = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path().join(DEPLOYMENT_FILE);
+        if !path.exists() {
+            continue;
+        }
+        match serde_json::from_str::<Deployment>(&fs::read_to_string(&path)?) {
+            Ok(deployment) => deployments.push(deployment),
+            Err(e) => StdErr::warn(&format!(
+                "Ignoring unreadable deployment {}: {}",
+                path.display(),
+                e
+            )),
+        }
+    }
+    deployments.sort_by_key(|deployment| deployment.id);
+
+    Ok(deployments)
+}
+
+/// The upload form of a recorded deployment.
+pub fn load_parts(home: &Path, target: &Target, deployment: &Deployment) -> Result<Vec<FormPart>> {
+    let dir = history_dir(home, target)?.join(deployment.id.to_string());
+
+    deployment
+        .parts
+        .iter()
+        .map(|part| {
+            let path = dir.join(&part.path);
+            anyhow::ensure!(
+                path.exists(),
+                "Deployment {} is missing {}",
+                deployment.id,
+                path.display()
+            );
+            Ok(FormPart {
+                name: part.name.clone(),
+                file_name: part.file_name.clone(),
+                content_type: part.content_type.clone(),
+                content: PartContent::File(path),
+            })
+        })
+        .collect()
+}
+
+/// Record the upload form that has just been published. Failing to do so only
+/// means it can't be rolled back to, so it isn't an error.
+pub fn record(home: &Path, target: &Target, parts: &[FormPart], rollback_of: Option<u32>) {
+    let result = history_dir(home, target).and_then(|dir| {
+        let previous = load(home, target)?;
+        let id = previous.last().map_or(1, |deployment| deployment.id + 1);
+        write(&dir.join(id.to_string()), id, parts, rollback_of)?;
+
+        let stale = (previous.len() + 1).saturating_sub(MAX_DEPLOYMENTS);
+        for deployment in &previous[..stale] {
+            fs::remove_dir_all(dir.join(deployment.id.to_string()))?;
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        StdErr::warn(&format!("Could not record this deployment: {}", e));
+    }
+}
+
+fn write(dir: &Path, id: u32, parts: &[FormPart], rollback_of: Option<u32>) -> Result<()> {
+    fs::create_dir_all(dir)?;
+
+    let mut stored = Vec::new();
+    for (index, part) in parts.iter().enumerate() {
+        // parts are stored by position, since their names can be module paths
+        let (path, file_name) = match &part.content {
+            PartContent::Text(text) => {
+                let path = PathBuf::from(index.to_string());
+                fs::write(dir.join(&path), text)?;
+                (path, part.file_name.clone())
+            }
+            PartContent::File(source) => {
+                // keep the extension, which the content type is guessed from
+                let mut path = PathBuf::from(index.to_string());
+                if let Some(extension) = source.extension() {
+                    path.set_extension(extension);
+                }
+                fs::copy(source, dir.join(&path))?;
+                let file_name = part.file_name.clone().or_else(|| {
+                    source
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                });
+                (path, file_name)
+            }
+        };
+        stored.push(StoredPart {
+            name: part.name.clone(),
+            file_name,
+            content_type: part.content_type.clone(),
+            path,
+        });
+    }
+
+    let deployment = Deployment {
+        id,
+        published_on: chrono::Utc::now().to_rfc3339(),
+        git_sha: git_sha(),
+        rollback_of,
+        parts: stored,
+    };
+    fs::write(
+        dir.join(DEPLOYMENT_FILE),
+        serde_json::to_string_pretty(&deployment)?,
+    )?;
+
+    Ok(())
+}
+
+fn git_sha() -> Option<String> {
+    let output = Command::new("git")
+        .args(&["rev-parse", "HEAD"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_stores_parts_to_be_published_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("worker.js");
+        fs::write(&script, "addEventListener()").unwrap();
+        let parts = vec![
+            FormPart::text("metadata", "metadata.json", "application/json", "{}".into()),
+            FormPart::file("script", script),
+        ];
+
+        let stored = dir.path().join("1");
+        write(&stored, 1, &parts, None).unwrap();
+        let deployment: Deployment =
+            serde_json::from_str(&fs::read_to_string(stored.join(DEPLOYMENT_FILE)).unwrap())
+                .unwrap();
+
+        assert_eq!(deployment.id, 1);
+        assert_eq!(
+            deployment.parts[1],
+            StoredPart {
+                name: "script".to_string(),
+                file_name: Some("worker.js".to_string()),
+                content_type: None,
+                path: PathBuf::from("1.js"),
+            }
+        );
+        assert_eq!(
+            fs::read_to_string(stored.join("1.js")).unwrap(),
+            "addEventListener()"
+        );
+        assert_eq!(fs::read_to_string(stored.join("0")).unwrap(), "{}");
+    }
+}
@@ -0,0 +1,244 @@
+pub mod history;
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+use crate::deploy::{self, DeployResults, DeploymentSet};
+use crate::http;
+use crate::settings::get_wrangler_home_dir;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr, StdOut};
+use crate::upload;
+use crate::upload::form::{FormPart, PartContent};
+
+use history::Deployment;
+
+/// Lists the deployments of the script recorded on this machine.
+pub fn list(target: &Target) -> Result<()> {
+    let deployments = history::load(&get_wrangler_home_dir(), target)?;
+    if deployments.is_empty() {
+        StdOut::info(&format!(
+            "{} hasn't been published from this machine yet",
+            target.name
+        ));
+        return Ok(());
+    }
+
+    let mut msg = format!("Deployments of {}, newest last:", target.name);
+    for deployment in &deployments {
+        msg.push_str(&format!(
+            "\n {:>3}  {}  {}",
+            deployment.id,
+            deployment.published_on,
+            deployment
+                .git_sha
+                .as_deref()
+                .map_or("-", |sha| &sha[..7.min(sha.len())])
+        ));
+        if let Some(id) = deployment.rollback_of {
+            msg.push_str(&format!("  (rollback to {})", id));
+        }
+    }
+    StdOut::info(&msg);
+
+    Ok(())
+}
+
+/// Publishes a recorded deployment again, and deploys it to the routes,
+/// domains and schedules the configuration has now.
+pub fn rollback(
+    user: &GlobalUser,
+    target: &Target,
+    deployments: DeploymentSet,
+    to: Option<u32>,
+) -> Result<()> {
+    let home = get_wrangler_home_dir();
+    let recorded = history::load(&home, target)?;
+    let deployment = rollback_target(&recorded, to)?;
+    let mut parts = history::load_parts(&home, target, deployment)?;
+    without_migrations(&mut parts)?;
+
+    StdErr::working(&format!(
+        "Rolling back {} to deployment {}, published on {}",
+        target.name, deployment.id, deployment.published_on
+    ));
+    let upload_client = http::legacy_auth_client(user);
+    let results = upload_and_deploy(
+        &home,
+        target,
+        &parts,
+        deployment.id,
+        || upload::script_parts(&upload_client, target, parts.clone()),
+        || deploy::deploy(user, &deployments),
+    )?;
+
+    let mut msg = format!(
+        "Rolled back {} to deployment {}",
+        target.name, deployment.id
+    );
+    if !results.urls.is_empty() {
+        msg.push_str(&format!(" on\n {}", results.urls.join("\n ")));
+    }
+    StdErr::success(&msg);
+
+    Ok(())
+}
+
+// As when publishing, the script is live as soon as it's uploaded, so it's
+// recorded then; the next rollback starts from the last deployment recorded
+fn upload_and_deploy(
+    home: &Path,
+    target: &Target,
+    parts: &[FormPart],
+    rollback_of: u32,
+    upload: impl FnOnce() -> Result<()>,
+    deploy: impl FnOnce() -> Result<DeployResults>,
+) -> Result<DeployResults> {
+    upload()?;
+    history::record(home, target, parts, Some(rollback_of));
+
+    deploy()
+}
+
+/// The deployment `--to` names, or else the one before the deployment that
+/// is currently published.
+fn rollback_target(history: &[Deployment], to: Option<u32>) -> Result<&Deployment> {
+    let current = history
+        .last()
+        .ok_or_else(|| anyhow!("There are no deployments recorded on this machine to roll back to; deployments are recorded when you publish"))?;
+
+    match to {
+        Some(id) => history.iter().find(|d| d.id == id).ok_or_else(|| {
+            anyhow!(
+                "There is no deployment {}; run `wrangler deployments list` to see them",
+                id
+            )
+        }),
+        None => {
+            // rolling back twice shouldn't undo the first rollback
+            let published = current.rollback_of.unwrap_or(current.id);
+            history
+                .iter()
+                .rev()
+                .find(|d| d.id < published)
+                .ok_or_else(|| anyhow!("There is no deployment before deployment {}", published))
+        }
+    }
+}
+
+// The script's migration tag has moved on since, so applying the deployment's
+// migrations again would fail.
+fn without_migrations(parts: &mut [FormPart]) -> Result<()> {
+    let metadata = match parts.iter_mut().find(|part| part.name == "metadata") {
+        Some(metadata) => metadata,
+        None => return Ok(()),
+    };
+    let mut value: Value = match &metadata.content {
+        PartContent::Text(text) => serde_json::from_str(text)?,
+        PartContent::File(path) => serde_json::from_str(&fs::read_to_string(path)?)?,
+    };
+
+    if let Some(table) = value.as_object_mut() {
+        if table.remove("migrations").map_or(false, |m| !m.is_null()) {
+            StdErr::warn("Durable Object migrations aren't rolled back; the deployment is published with the classes the script has now");
+        }
+        let is_site = table
+            .get("bindings")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .any(|binding| binding["name"] == "__STATIC_CONTENT_MANIFEST");
+        if is_site {
+            StdErr::warn("Workers Sites files deleted by later publishes aren't restored");
+        }
+    }
+    metadata.content = PartContent::Text(value.to_string());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deployment(id: u32, rollback_of: Option<u32>) -> Deployment {
+        Deployment {
+            id,
+            published_on: "2022-01-01T00:00:00+00:00".to_string(),
+            git_sha: None,
+            rollback_of,
+            parts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn it_rolls_back_to_the_previous_deployment() {
+        let history = vec![
+            deployment(1, None),
+            deployment(2, None),
+            deployment(3, None),
+        ];
+
+        assert_eq!(rollback_target(&history, None).unwrap().id, 2);
+        assert_eq!(rollback_target(&history, Some(1)).unwrap().id, 1);
+        assert!(rollback_target(&history, Some(4)).is_err());
+        assert!(rollback_target(&history[..1], None).is_err());
+        assert!(rollback_target(&[], None).is_err());
+    }
+
+    #[test]
+    fn it_rolls_back_past_earlier_rollbacks() {
+        let history = vec![
+            deployment(1, None),
+            deployment(2, None),
+            deployment(3, None),
+            deployment(4, Some(2)),
+        ];
+
+        assert_eq!(rollback_target(&history, None).unwrap().id, 1);
+    }
+
+    #[test]
+    fn it_records_rollbacks_whose_deploy_fails() {
+        let home = tempfile::tempdir().unwrap();
+        let target = Target {
+            name: "worker".to_string(),
+            account_id: Some("account".to_string()).into(),
+            ..Default::default()
+        };
+        let parts = vec![FormPart::text(
+            "metadata",
+            "metadata.json",
+            "application/json",
+            "{}".to_string(),
+        )];
+
+        let failed_upload = upload_and_deploy(
+            home.path(),
+            &target,
+            &parts,
+            2,
+            || Err(anyhow!("upload failed")),
+            || Ok(DeployResults::default()),
+        );
+        assert!(failed_upload.is_err());
+        assert!(history::load(home.path(), &target).unwrap().is_empty());
+
+        let failed_deploy = upload_and_deploy(
+            home.path(),
+            &target,
+            &parts,
+            2,
+            || Ok(()),
+            || Err(anyhow!("route creation failed")),
+        );
+        assert!(failed_deploy.is_err());
+        let recorded = history::load(home.path(), &target).unwrap();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].rollback_of, Some(2));
+    }
+}
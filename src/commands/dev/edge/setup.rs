@@ -137,11 +137,11 @@ fn get_session_config(target: &DeployTarget) -> serde_json::Value {
     }
 }
 
-fn get_session_address(target: &DeployTarget) -> Result<String> {
+fn get_session_address(target: &DeployTarget, user: &GlobalUser) -> Result<String> {
     let addr = match target {
         DeployTarget::Zoned(config) => format!(
             "https://api.cloudflare.com/client/v4/zones/{}/workers/edge-preview",
-            config.zone_id
+            config.zone.id(user)?
         ),
         // TODO: zoneless is probably wrong
         DeployTarget::Zoneless(config) => format!(
@@ -163,7 +163,7 @@ fn get_upload_address(target: &mut Target) -> Result<String> {
 
 fn get_exchange_url(deploy_target: &DeployTarget, user: &GlobalUser) -> Result<Url> {
     let client = crate::http::legacy_auth_client(user);
-    let address = get_session_address(deploy_target)?;
+    let address = get_session_address(deploy_target, user)?;
     let url = Url::parse(&address)?;
     let response = client.get(url).send()?.error_for_status()?;
     let text = &response.text()?;
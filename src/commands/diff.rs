@@ -0,0 +1,470 @@
+use std::fmt;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::deploy::{self, DeployTarget, DeploymentSet};
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::{Route, Target};
+use crate::upload;
+use crate::upload::form::PartContent;
+
+/// Bindings wrangler adds to Workers Sites while publishing, which can't be
+/// compared before the site is uploaded.
+const SITE_BINDINGS: [&str; 2] = ["__STATIC_CONTENT", "__STATIC_CONTENT_MANIFEST"];
+
+/// Script settings compared on their own, in the order they are shown.
+const SETTINGS: [&str; 3] = ["usage_model", "compatibility_date", "compatibility_flags"];
+
+/// A difference between the configuration and the deployed worker.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    Added(String),
+    Removed(String),
+    Changed(String),
+    /// Something publishing leaves as it is, but that doesn't match the configuration
+    Kept(String),
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added(change) => write!(f, "+ {}", change),
+            Change::Removed(change) => write!(f, "- {}", change),
+            Change::Changed(change) => write!(f, "~ {}", change),
+            Change::Kept(change) => write!(f, "! {}", change),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SettingsResponse {
+    result: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct SchedulesResponse {
+    result: Schedules,
+}
+
+#[derive(Debug, Deserialize)]
+struct Schedules {
+    schedules: Vec<Schedule>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Schedule {
+    cron: String,
+}
+
+/// Compares the upload form and deployments of `target` with what is
+/// currently deployed. The script's code isn't compared.
+pub fn diff(
+    user: &GlobalUser,
+    target: &Target,
+    deployments: &DeploymentSet,
+    prune_routes: bool,
+) -> Result<Vec<Change>> {
+    let mut changes = Vec::new();
+
+    let local = local_metadata(target)?;
+    let remote = remote_settings(user, target)?;
+    if remote.is_none() {
+        changes.push(Change::Added(format!("script {}", target.name)));
+    }
+    let remote = remote.unwrap_or(Value::Null);
+
+    changes.extend(diff_bindings(
+        bindings(&local).as_slice(),
+        bindings(&remote).as_slice(),
+    ));
+    for setting in &SETTINGS {
+        let local = &local[*setting];
+        // the API keeps the current usage model when none is given
+        if *setting == "usage_model" && local.is_null() {
+            continue;
+        }
+        changes.extend(diff_setting(setting, local, &remote[*setting]));
+    }
+
+    for deployment in deployments {
+        match deployment {
+            DeployTarget::Zoned(zoned) => {
+                let zone_id = zoned.zone.id(user)?;
+                let existing = deploy::fetch_all(user, &zone_id)?;
+                changes.extend(diff_routes(
+                    &target.name,
+                    &zoned.routes,
+                    &existing,
+                    prune_routes,
+                ));
+            }
+            DeployTarget::Schedule(schedule) => {
+                let existing = if remote.is_null() {
+                    Vec::new()
+                } else {
+                    remote_schedules(user, target)?
+                };
+                changes.extend(diff_schedules(&schedule.crons, &existing));
+            }
+            DeployTarget::Zoneless(_) | DeployTarget::CustomDomain(_) => {}
+        }
+    }
+
+    Ok(changes)
+}
+
+fn local_metadata(target: &Target) -> Result<Value> {
+    let parts = upload::form::build_parts(target, None, None)?;
+    match parts
+        .iter()
+        .find(|part| part.name == "metadata")
+        .map(|part| &part.content)
+    {
+        Some(PartContent::Text(metadata)) => Ok(serde_json::from_str(metadata)?),
+        _ => anyhow::bail!("The upload form of {} has no metadata", target.name),
+    }
+}
+
+// None if the script hasn't been published yet
+fn remote_settings(user: &GlobalUser, target: &Target) -> Result<Option<Value>> {
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}/settings",
+        target.account_id.load()?,
+        target.name,
+    );
+
+    let res = http::send(http::legacy_auth_client(user).get(&addr))?;
+    let status = res.status();
+    let text = res.text()?;
+    if status == reqwest::StatusCode::NOT_FOUND || is_missing_script(&text) {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        anyhow::bail!(crate::format_api_errors(text))
+    }
+
+    let settings: SettingsResponse = serde_json::from_str(&text)?;
+    Ok(Some(settings.result))
+}
+
+// the API's error code for a script that doesn't exist
+fn is_missing_script(response: &str) -> bool {
+    serde_json::from_str::<Value>(response)
+        .ok()
+        .and_then(|response| response["errors"].as_array().cloned())
+        .unwrap_or_default()
+        .iter()
+        .any(|error| error["code"] == 10007)
+}
+
+fn remote_schedules(user: &GlobalUser, target: &Target) -> Result<Vec<String>> {
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}/schedules",
+        target.account_id.load()?,
+        target.name,
+    );
+
+    let res = http::send(http::legacy_auth_client(user).get(&addr))?;
+    let status = res.status();
+    let text = res.text()?;
+    if !status.is_success() {
+        anyhow::bail!(crate::format_api_errors(text))
+    }
+
+    let schedules: SchedulesResponse = serde_json::from_str(&text)?;
+    Ok(schedules
+        .result
+        .schedules
+        .into_iter()
+        .map(|schedule| schedule.cron)
+        .collect())
+}
+
+fn bindings(metadata: &Value) -> Vec<Value> {
+    metadata["bindings"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|binding| {
+            let name = binding["name"].as_str().unwrap_or_default();
+            // secrets aren't part of the upload form, so they always look removed
+            binding["type"] != "secret_text" && !SITE_BINDINGS.contains(&name)
+        })
+        .collect()
+}
+
+/// Compares bindings by name. Only the fields the local binding sets are
+/// compared, since the API adds some of its own.
+fn diff_bindings(local: &[Value], remote: &[Value]) -> Vec<Change> {
+    let name = |binding: &Value| binding["name"].as_str().unwrap_or_default().to_string();
+    let kind = |binding: &Value| binding["type"].as_str().unwrap_or_default().to_string();
+    let mut changes = Vec::new();
+
+    for binding in local {
+        let described = format!("binding {} ({})", name(binding), kind(binding));
+        let deployed = match remote.iter().find(|r| name(r) == name(binding)) {
+            Some(deployed) => deployed,
+            None => {
+                changes.push(Change::Added(described));
+                continue;
+            }
+        };
+
+        if kind(deployed) != kind(binding) {
+            changes.push(Change::Changed(format!(
+                "binding {}: {} -> {}",
+                name(binding),
+                kind(deployed),
+                kind(binding)
+            )));
+            continue;
+        }
+
+        let fields: Vec<String> = binding
+            .as_object()
+            .into_iter()
+            .flatten()
+            // parts are named by the upload form, and unset fields take the API's default
+            .filter(|(key, value)| {
+                !["name", "type", "part"].contains(&key.as_str()) && !value.is_null()
+            })
+            .filter(|(key, value)| deployed.get(key.as_str()).unwrap_or(&Value::Null) != *value)
+            .map(|(key, value)| {
+                format!(
+                    "{} {} -> {}",
+                    key,
+                    deployed.get(key.as_str()).unwrap_or(&Value::Null),
+                    value
+                )
+            })
+            .collect();
+        if !fields.is_empty() {
+            changes.push(Change::Changed(format!(
+                "{}: {}",
+                described,
+                fields.join(", ")
+            )));
+        }
+    }
+
+    for binding in remote {
+        if !local.iter().any(|l| name(l) == name(binding)) {
+            changes.push(Change::Removed(format!(
+                "binding {} ({})",
+                name(binding),
+                kind(binding)
+            )));
+        }
+    }
+
+    changes
+}
+
+fn diff_setting(name: &str, local: &Value, remote: &Value) -> Option<Change> {
+    // an empty list of flags is the same as none
+    let normalize = |value: &Value| match value {
+        Value::Array(values) if values.is_empty() => Value::Null,
+        value => value.clone(),
+    };
+    let (local, remote) = (normalize(local), normalize(remote));
+
+    match (&remote, &local) {
+        (remote, local) if remote == local => None,
+        (Value::Null, local) => Some(Change::Added(format!("{} {}", name, local))),
+        (remote, Value::Null) => Some(Change::Removed(format!("{} {}", name, remote))),
+        (remote, local) => Some(Change::Changed(format!("{} {} -> {}", name, remote, local))),
+    }
+}
+
+fn diff_schedules(local: &[String], remote: &[String]) -> Vec<Change> {
+    let added = local
+        .iter()
+        .filter(|cron| !remote.contains(cron))
+        .map(|cron| Change::Added(format!("schedule {}", cron)));
+    let removed = remote
+        .iter()
+        .filter(|cron| !local.contains(cron))
+        .map(|cron| Change::Removed(format!("schedule {}", cron)));
+
+    added.chain(removed).collect()
+}
+
+/// Routes are only ever created by publishing, unless stale routes of this
+/// worker are pruned; routes of other workers are always left alone.
+fn diff_routes(
+    script_name: &str,
+    local: &[Route],
+    remote: &[Route],
+    prune_routes: bool,
+) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    for route in local {
+        match remote.iter().find(|r| r.pattern == route.pattern) {
+            None => changes.push(Change::Added(format!("route {}", route.pattern))),
+            Some(existing) if existing.script == route.script => {}
+            Some(existing) => changes.push(Change::Kept(format!(
+                "route {} is already pointing to {}, and won't be changed",
+                route.pattern,
+                existing.script.as_deref().unwrap_or("no worker")
+            ))),
+        }
+    }
+
+    for stale in deploy::stale_routes(script_name, local, remote) {
+        changes.push(if prune_routes {
+            Change::Removed(format!("route {}", stale.pattern))
+        } else {
+            Change::Kept(format!(
+                "route {} points to {} but isn't in your configuration file",
+                stale.pattern, script_name
+            ))
+        });
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    fn route(pattern: &str, script: Option<&str>) -> Route {
+        Route {
+            id: None,
+            script: script.map(str::to_string),
+            pattern: pattern.to_string(),
+        }
+    }
+
+    #[test]
+    fn it_diffs_bindings_by_name() {
+        let local = vec![
+            json!({"type": "plain_text", "name": "MODE", "text": "production"}),
+            json!({"type": "kv_namespace", "name": "CACHE", "namespace_id": "abc"}),
+            json!({"type": "durable_object_namespace", "name": "ROOMS", "class_name": "Room", "script_name": null}),
+            json!({"type": "wasm_module", "name": "WASM", "part": "WASM"}),
+        ];
+        let remote = vec![
+            json!({"type": "plain_text", "name": "MODE", "text": "staging"}),
+            json!({"type": "durable_object_namespace", "name": "ROOMS", "class_name": "Room", "namespace_id": "xyz"}),
+            json!({"type": "wasm_module", "name": "WASM"}),
+            json!({"type": "r2_bucket", "name": "ASSETS", "bucket_name": "assets"}),
+        ];
+
+        assert_eq!(
+            diff_bindings(&local, &remote),
+            vec![
+                Change::Changed(
+                    "binding MODE (plain_text): text \"staging\" -> \"production\"".to_string()
+                ),
+                Change::Added("binding CACHE (kv_namespace)".to_string()),
+                Change::Removed("binding ASSETS (r2_bucket)".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_ignores_secrets_and_site_bindings() {
+        let metadata = json!({
+            "bindings": [
+                {"type": "secret_text", "name": "TOKEN"},
+                {"type": "kv_namespace", "name": "__STATIC_CONTENT", "namespace_id": "abc"},
+                {"type": "plain_text", "name": "MODE", "text": "production"},
+            ]
+        });
+
+        assert_eq!(
+            bindings(&metadata),
+            vec![json!({"type": "plain_text", "name": "MODE", "text": "production"})]
+        );
+    }
+
+    #[test]
+    fn it_diffs_settings() {
+        assert_eq!(
+            diff_setting(
+                "compatibility_date",
+                &json!("2022-02-02"),
+                &json!("2022-01-01")
+            ),
+            Some(Change::Changed(
+                "compatibility_date \"2022-01-01\" -> \"2022-02-02\"".to_string()
+            ))
+        );
+        assert_eq!(
+            diff_setting("compatibility_flags", &Value::Null, &json!([])),
+            None
+        );
+        assert_eq!(
+            diff_setting("usage_model", &json!("unbound"), &Value::Null),
+            Some(Change::Added("usage_model \"unbound\"".to_string()))
+        );
+    }
+
+    #[test]
+    fn it_diffs_schedules() {
+        let local = vec!["0 * * * *".to_string(), "*/5 * * * *".to_string()];
+        let remote = vec!["0 * * * *".to_string(), "0 0 * * *".to_string()];
+
+        assert_eq!(
+            diff_schedules(&local, &remote),
+            vec![
+                Change::Added("schedule */5 * * * *".to_string()),
+                Change::Removed("schedule 0 0 * * *".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_diffs_routes() {
+        let local = vec![
+            route("example.com/*", Some("worker")),
+            route("example.com/api/*", Some("worker")),
+            route("example.com/blog/*", Some("worker")),
+        ];
+        let remote = vec![
+            route("example.com/*", Some("worker")),
+            route("example.com/blog/*", Some("blog")),
+            route("example.com/old/*", Some("worker")),
+            route("example.com/static/*", None),
+        ];
+
+        assert_eq!(
+            diff_routes("worker", &local, &remote, false),
+            vec![
+                Change::Added("route example.com/api/*".to_string()),
+                Change::Kept(
+                    "route example.com/blog/* is already pointing to blog, and won't be changed"
+                        .to_string()
+                ),
+                Change::Kept(
+                    "route example.com/old/* points to worker but isn't in your configuration file"
+                        .to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            diff_routes("worker", &local, &remote, true).last(),
+            Some(&Change::Removed("route example.com/old/*".to_string()))
+        );
+    }
+
+    #[test]
+    fn it_serializes_changes_by_kind() {
+        assert_eq!(
+            serde_json::to_value(&Change::Kept(
+                "route example.com/* (script other)".to_string()
+            ))
+            .unwrap(),
+            json!({ "kept": "route example.com/* (script other)" })
+        );
+    }
+}
@@ -3,11 +3,17 @@ use std::path::{Path, PathBuf};
 use anyhow::Result;
 
 use crate::commands::validate_worker_name;
-use crate::settings::toml::{Manifest, Site, TargetType};
+use crate::settings::toml::{ConfigFormat, Manifest, Site, TargetType};
 use crate::terminal::message::{Message, StdOut};
-pub fn init(name: Option<&str>, target_type: Option<TargetType>, site_flag: bool) -> Result<()> {
-    if Path::new("./wrangler.toml").exists() {
-        if site_flag {
+pub fn init(
+    name: Option<&str>,
+    target_type: Option<TargetType>,
+    site_flag: bool,
+    format: ConfigFormat,
+) -> Result<()> {
+    if let Some(existing) = ConfigFormat::existing(Path::new("./")) {
+        let file_name = existing.file_name().unwrap().to_string_lossy();
+        if site_flag && ConfigFormat::from_path(&existing) == ConfigFormat::Toml {
             let msg = r#"A wrangler.toml file already exists!
 
 To add Workers Sites to your existing wrangler.toml, please add this section:
@@ -18,8 +24,16 @@ entry-point = "workers-site"
 
 "#;
             anyhow::bail!(msg);
+        } else if site_flag {
+            anyhow::bail!(
+                "A {} file already exists! To add Workers Sites to it, please add a `site` section with a `bucket` pointing to the directory with static assets.",
+                file_name
+            );
         } else {
-            anyhow::bail!("A wrangler.toml file already exists! Please remove it before running this command again.");
+            anyhow::bail!(
+                "A {} file already exists! Please remove it before running this command again.",
+                file_name
+            );
         }
     }
     let dirname = get_current_dirname()?;
@@ -36,15 +50,22 @@ entry-point = "workers-site"
             Some(target_type),
             &config_path,
             Some(site.clone()),
+            format,
         )?;
 
         site.scaffold_worker()?;
         StdOut::success("Successfully scaffolded workers site");
     } else {
-        Manifest::generate(name.to_string(), Some(target_type), &config_path, None)?;
+        Manifest::generate(
+            name.to_string(),
+            Some(target_type),
+            &config_path,
+            None,
+            format,
+        )?;
     }
 
-    StdOut::success("Succesfully created a `wrangler.toml`");
+    StdOut::success(&format!("Succesfully created a `{}`", format.file_name()));
     Ok(())
 }
 
@@ -95,14 +95,13 @@ fn get_response(
     let client = http::legacy_auth_client(user);
     let value_body = get_request_body(&data)?;
     let res = match &data.metadata {
-        Some(metadata) => {
-            let value_part = multipart::Part::bytes(value_body);
+        Some(metadata) => http::send_rebuilt(&client, || {
             let form = multipart::Form::new()
-                .part("value", value_part)
+                .part("value", multipart::Part::bytes(value_body.clone()))
                 .text("metadata", metadata.to_string());
-            client.put(&url_into_str).multipart(form).send()?
-        }
-        None => client.put(&url_into_str).body(value_body).send()?,
+            Ok(client.put(&url_into_str).multipart(form))
+        })?,
+        None => http::send(client.put(&url_into_str).body(value_body))?,
     };
     Ok(res)
 }
@@ -7,6 +7,7 @@ use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
 
 use crate::http;
 use crate::settings::toml::Target;
+use crate::ApiError;
 
 pub mod bulk;
 pub mod key;
@@ -17,6 +18,10 @@ pub fn format_error(e: ApiFailure) -> String {
     http::format_error(e, Some(&kv_help))
 }
 
+pub fn api_error(e: ApiFailure) -> ApiError {
+    http::api_error(e, Some(&kv_help))
+}
+
 // kv_help() provides more detailed explanations of Workers KV API error codes.
 // See https://api.cloudflare.com/#workers-kv-namespace-errors for details.
 fn kv_help(error_code: u16) -> &'static str {
@@ -104,6 +109,7 @@ mod tests {
                 },
             ],
             r2_buckets: Vec::new(),
+            services: Vec::new(),
             durable_objects: None,
             migrations: None,
             name: "test-target".to_string(),
@@ -111,6 +117,7 @@ mod tests {
             webpack_config: None,
             site: None,
             vars: None,
+            secrets: None,
             text_blobs: None,
             build: None,
             wasm_modules: None,
@@ -0,0 +1,63 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use crate::settings::get_wrangler_home_dir;
+use crate::settings::toml::migrations::MigrationConfig;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr};
+
+// The migrations applied by the last publish from this machine, per script. The API
+// only reports a script's current tag, not the steps that led to it.
+fn history_path(target: &Target) -> Result<PathBuf> {
+    Ok(get_wrangler_home_dir()
+        .join("migrations")
+        .join(target.account_id.load()?)
+        .join(format!("{}.json", target.name)))
+}
+
+/// The migrations recorded as applied to the script, or none if it hasn't
+/// been published with a list of migrations from this machine.
+pub fn load(target: &Target) -> Result<Vec<MigrationConfig>> {
+    let path = history_path(target)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    match serde_json::from_str(&fs::read_to_string(&path)?) {
+        Ok(applied) => Ok(applied),
+        Err(e) => {
+            StdErr::warn(&format!(
+                "Ignoring unreadable migration history {}: {}",
+                path.display(),
+                e
+            ));
+            Ok(Vec::new())
+        }
+    }
+}
+
+/// Record the migrations that have just been applied to the script. Failing to
+/// do so only means the next publish can't check them, so it isn't an error.
+pub fn record(target: &Target) {
+    let applied = match target
+        .migrations
+        .as_ref()
+        .and_then(|migrations| migrations.applied_after_publish())
+    {
+        Some(applied) => applied,
+        None => return,
+    };
+
+    let result = history_path(target).and_then(|path| {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(applied)?)?;
+        Ok(())
+    });
+    if let Err(e) = result {
+        StdErr::warn(&format!("Could not record the applied migrations: {}", e));
+    }
+}
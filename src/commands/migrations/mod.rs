@@ -0,0 +1,6 @@
+pub mod history;
+mod new;
+mod plan;
+
+pub use new::new;
+pub use plan::plan;
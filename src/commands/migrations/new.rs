@@ -0,0 +1,388 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use toml_edit::{value, ArrayOfTables, Document, Item, Table, Value};
+
+use crate::settings::toml::migrations::{
+    DurableObjectsMigration, Migration, MigrationConfig, Migrations,
+};
+use crate::settings::toml::{ConfigFormat, Target};
+use crate::terminal::message::{Message, StdOut};
+
+/// Append a `[[migrations]]` entry with the next tag to the configuration
+/// file, after checking that every class it touches is declared.
+pub fn new(
+    config_path: &Path,
+    target: &Target,
+    environment: Option<&str>,
+    migration: DurableObjectsMigration,
+    tag: Option<String>,
+) -> Result<()> {
+    anyhow::ensure!(
+        ConfigFormat::from_path(config_path) == ConfigFormat::Toml,
+        "`wrangler migrations new` only supports wrangler.toml files"
+    );
+
+    let history = match &target.migrations {
+        Some(Migrations::List { migrations, .. }) => migrations.as_slice(),
+        _ => &[],
+    };
+    let tag = match tag {
+        Some(tag) if history.iter().any(|m| m.tag == tag) => {
+            anyhow::bail!("The migration tag \"{}\" is already used", tag)
+        }
+        Some(tag) => tag,
+        None => next_tag(history),
+    };
+
+    check_classes(&migration, &declared_classes(target), history)?;
+
+    let source = fs::read_to_string(config_path)?;
+    let config = MigrationConfig {
+        tag,
+        migration: Migration {
+            durable_objects: migration,
+        },
+    };
+    let updated = append(&source, environment, &config)?;
+    fs::write(config_path, updated)?;
+
+    StdOut::success(&format!(
+        "Added migration \"{}\" to {}. Run `wrangler migrations plan` to review it before publishing.",
+        config.tag,
+        config_path.display()
+    ));
+    Ok(())
+}
+
+/// Increments the number at the end of the last tag, keeping its prefix and
+/// padding, so `v1` is followed by `v2` and `migration-009` by `migration-010`.
+fn next_tag(history: &[MigrationConfig]) -> String {
+    let (prefix, mut number, width) = match history.last() {
+        Some(last) => {
+            let prefix = last.tag.trim_end_matches(|c: char| c.is_ascii_digit());
+            let digits = &last.tag[prefix.len()..];
+            match digits.parse::<u64>() {
+                Ok(number) => (prefix.to_string(), number + 1, digits.len()),
+                Err(_) => ("v".to_string(), history.len() as u64 + 1, 1),
+            }
+        }
+        None => ("v".to_string(), 1, 1),
+    };
+
+    loop {
+        let tag = format!("{}{:0width$}", prefix, number, width = width);
+        if !history.iter().any(|m| m.tag == tag) {
+            return tag;
+        }
+        number += 1;
+    }
+}
+
+// classes implemented by this script, as opposed to bindings to another script's classes
+fn declared_classes(target: &Target) -> HashSet<String> {
+    target
+        .durable_objects
+        .as_ref()
+        .and_then(|durable_objects| durable_objects.classes.as_ref())
+        .into_iter()
+        .flatten()
+        .filter(|class| {
+            class
+                .script_name
+                .as_ref()
+                .map_or(true, |script| script == &target.name)
+        })
+        .map(|class| class.class_name.clone())
+        .collect()
+}
+
+fn check_classes(
+    migration: &DurableObjectsMigration,
+    declared: &HashSet<String>,
+    history: &[MigrationConfig],
+) -> Result<()> {
+    // classes the existing migrations leave behind can still be deleted or
+    // renamed after their binding has been removed
+    let mut existing = HashSet::new();
+    for step in history {
+        let step = &step.migration.durable_objects;
+        existing.extend(step.new_classes.iter().cloned());
+        for class in &step.deleted_classes {
+            existing.remove(class);
+        }
+        for rename in &step.renamed_classes {
+            existing.remove(&rename.from);
+            existing.insert(rename.to.clone());
+        }
+        existing.extend(step.transferred_classes.iter().map(|t| t.to.clone()));
+    }
+
+    let mut problems = Vec::new();
+    let targets = migration
+        .new_classes
+        .iter()
+        .chain(migration.renamed_classes.iter().map(|r| &r.to))
+        .chain(migration.transferred_classes.iter().map(|t| &t.to));
+    for class in targets {
+        if !declared.contains(class) {
+            problems.push(format!(
+                "class \"{}\" is not declared in `durable_objects.classes`",
+                class
+            ));
+        }
+    }
+    for class in &migration.new_classes {
+        if existing.contains(class) {
+            problems.push(format!(
+                "class \"{}\" was already created by an earlier migration",
+                class
+            ));
+        }
+    }
+    let sources = migration
+        .deleted_classes
+        .iter()
+        .chain(migration.renamed_classes.iter().map(|r| &r.from));
+    for class in sources {
+        if !existing.contains(class) && !declared.contains(class) {
+            problems.push(format!(
+                "class \"{}\" does not exist in `durable_objects.classes` or any earlier migration",
+                class
+            ));
+        }
+    }
+
+    if !problems.is_empty() {
+        anyhow::bail!("Could not add the migration:\n- {}", problems.join("\n- "))
+    }
+
+    Ok(())
+}
+
+/// Appends the migration to the `[[migrations]]` tables through toml_edit,
+/// which leaves everything already in the file as it was.
+fn append(source: &str, environment: Option<&str>, config: &MigrationConfig) -> Result<String> {
+    let mut doc = source
+        .parse::<Document>()
+        .map_err(|err| anyhow!("toml_edit failed to parse configuration file. {}", err))?;
+
+    // migrations go next to the environment's own migrations, if it has any
+    let env_migrations = environment.filter(|name| {
+        doc.as_table()
+            .get("env")
+            .and_then(Item::as_table)
+            .and_then(|envs| envs.get(name))
+            .and_then(Item::as_table)
+            .map_or(false, |env| env.contains_key("migrations"))
+    });
+    let (header, migrations) = match env_migrations {
+        Some(name) => (
+            format!("env.{}.migrations", key(name)),
+            &mut doc["env"][name]["migrations"],
+        ),
+        None => ("migrations".to_string(), &mut doc["migrations"]),
+    };
+
+    if migrations.is_none() {
+        *migrations = Item::ArrayOfTables(ArrayOfTables::new());
+    }
+    let migrations = migrations.as_array_of_tables_mut().ok_or_else(|| {
+        anyhow!(
+            "`{}` is an inline array; rewrite it as [[{}]] tables to add migrations automatically",
+            header,
+            header
+        )
+    })?;
+    migrations.append(table(config)?);
+
+    Ok(doc.to_string_in_original_order())
+}
+
+fn table(config: &MigrationConfig) -> Result<Table> {
+    let migration = &config.migration.durable_objects;
+    let mut table = Table::new();
+    table["tag"] = value(config.tag.as_str());
+
+    if !migration.new_classes.is_empty() {
+        table["new_classes"] = value(list(migration.new_classes.iter().map(|c| string(c)))?);
+    }
+    if !migration.deleted_classes.is_empty() {
+        table["deleted_classes"] =
+            value(list(migration.deleted_classes.iter().map(|c| string(c)))?);
+    }
+    if !migration.renamed_classes.is_empty() {
+        let renames = migration
+            .renamed_classes
+            .iter()
+            .map(|r| format!("{{ from = {}, to = {} }}", string(&r.from), string(&r.to)));
+        table["renamed_classes"] = value(list(renames)?);
+    }
+    if !migration.transferred_classes.is_empty() {
+        let transfers = migration.transferred_classes.iter().map(|t| {
+            format!(
+                "{{ from = {}, from_script = {}, to = {} }}",
+                string(&t.from),
+                string(&t.from_script),
+                string(&t.to)
+            )
+        });
+        table["transferred_classes"] = value(list(transfers)?);
+    }
+
+    Ok(table)
+}
+
+fn string(s: &str) -> String {
+    toml::Value::String(s.to_string()).to_string()
+}
+
+// An array of values written as TOML, such as inline tables
+fn list(values: impl Iterator<Item = String>) -> Result<Value> {
+    let values: Vec<String> = values.collect();
+    format!("[{}]", values.join(", "))
+        .parse::<Value>()
+        .map_err(|err| anyhow!("Could not add the migration: {}", err))
+}
+
+fn key(name: &str) -> String {
+    if !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        name.to_string()
+    } else {
+        string(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::str::FromStr;
+
+    use crate::settings::toml::migrations::RenameClass;
+    use crate::settings::toml::Manifest;
+
+    fn migration(tag: &str, durable_objects: DurableObjectsMigration) -> MigrationConfig {
+        MigrationConfig {
+            tag: tag.to_string(),
+            migration: Migration { durable_objects },
+        }
+    }
+
+    fn new_classes(classes: &[&str]) -> DurableObjectsMigration {
+        DurableObjectsMigration {
+            new_classes: classes.iter().map(|c| c.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn declared(classes: &[&str]) -> HashSet<String> {
+        classes.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn it_increments_the_last_tag() {
+        assert_eq!(next_tag(&[]), "v1");
+        assert_eq!(next_tag(&[migration("v1", Default::default())]), "v2");
+        assert_eq!(
+            next_tag(&[migration("migration-009", Default::default())]),
+            "migration-010"
+        );
+        assert_eq!(
+            next_tag(&[
+                migration("v3", Default::default()),
+                migration("initial", Default::default())
+            ]),
+            "v4"
+        );
+    }
+
+    #[test]
+    fn it_refuses_undeclared_classes() {
+        let history = vec![migration("v1", new_classes(&["Counter"]))];
+
+        let ok = DurableObjectsMigration {
+            renamed_classes: vec![RenameClass {
+                from: "Counter".to_string(),
+                to: "Tally".to_string(),
+            }],
+            ..Default::default()
+        };
+        assert!(check_classes(&ok, &declared(&["Tally"]), &history).is_ok());
+
+        let error = check_classes(&new_classes(&["Missing"]), &declared(&["Tally"]), &history)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("\"Missing\""), "{}", error);
+
+        let deleted = DurableObjectsMigration {
+            deleted_classes: vec!["Unknown".to_string()],
+            ..Default::default()
+        };
+        assert!(check_classes(&deleted, &declared(&[]), &history).is_err());
+
+        assert!(check_classes(
+            &new_classes(&["Counter"]),
+            &declared(&["Counter"]),
+            &history
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn it_appends_without_touching_the_rest_of_the_file() {
+        let source = r#"name = "worker" # my worker
+type = "javascript"
+
+[durable_objects]
+classes = [{ binding = "COUNTER", class_name = "Counter" }]
+
+[[migrations]]
+tag = "v1"
+new_classes = ["Counter"]
+"#;
+        let config = migration(
+            "v2",
+            DurableObjectsMigration {
+                renamed_classes: vec![RenameClass {
+                    from: "Counter".to_string(),
+                    to: "Tally".to_string(),
+                }],
+                ..Default::default()
+            },
+        );
+
+        let updated = append(source, None, &config).unwrap();
+
+        assert!(updated.starts_with(source));
+        let manifest = Manifest::from_str(&updated).unwrap();
+        let migrations = manifest.migrations.unwrap();
+        assert_eq!(migrations.len(), 2);
+        assert_eq!(migrations[1], config);
+    }
+
+    #[test]
+    fn it_appends_to_environment_migrations() {
+        let source = r#"name = "worker"
+type = "javascript"
+
+[[env.staging.migrations]]
+tag = "v1"
+new_classes = ["Counter"]
+"#;
+        let config = migration("v2", new_classes(&["Tally"]));
+
+        let updated = append(source, Some("staging"), &config).unwrap();
+
+        assert!(updated.contains("[[env.staging.migrations]]\ntag = \"v2\""));
+        let manifest = Manifest::from_str(&updated).unwrap();
+        let staging = &manifest.env.unwrap()["staging"];
+        assert_eq!(staging.migrations.as_ref().unwrap()[1], config);
+    }
+}
@@ -0,0 +1,176 @@
+use anyhow::Result;
+
+use crate::commands::migrations::history;
+use crate::commands::publish::get_migration_tag;
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::migrations::{ApiMigration, MigrationTag, Migrations};
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::styles;
+
+/// Print the Durable Object migrations `wrangler publish` would apply to the
+/// script, without uploading anything.
+pub fn plan(user: &GlobalUser, target: &Target) -> Result<()> {
+    let mut migrations = match &target.migrations {
+        Some(migrations) => migrations.clone(),
+        None => {
+            StdOut::info("Your configuration file has no migrations");
+            return Ok(());
+        }
+    };
+
+    let client = http::legacy_auth_client(user);
+    let script_tag = get_migration_tag(&client, target)?;
+    match &script_tag {
+        MigrationTag::HasTag(tag) => StdOut::info(&format!("Current migration tag: {}", tag)),
+        MigrationTag::NoScript => StdOut::info(&format!(
+            "{} has not been published yet, so every migration is pending",
+            target.name
+        )),
+        _ => StdOut::info("The script has no migration tag yet"),
+    }
+    match &mut migrations {
+        Migrations::Adhoc {
+            script_tag: tag, ..
+        } => *tag = script_tag,
+        Migrations::List {
+            script_tag: tag, ..
+        } => *tag = script_tag,
+    };
+    migrations.verify_history(&history::load(target)?)?;
+
+    let api_migration = match migrations.api_migration()? {
+        Some(api_migration) => api_migration,
+        None => {
+            StdOut::success("No pending migrations");
+            return Ok(());
+        }
+    };
+
+    if let Some(new_tag) = api_migration.new_tag() {
+        StdOut::info(&format!("New migration tag: {}", new_tag));
+    }
+    for (line, destructive) in describe(&api_migration) {
+        if destructive {
+            println!("{}", styles::warning(line));
+        } else {
+            println!("{}", line);
+        }
+    }
+
+    let deleted: Vec<&str> = api_migration
+        .steps()
+        .iter()
+        .flat_map(|step| &step.durable_objects.deleted_classes)
+        .map(String::as_str)
+        .collect();
+    if !deleted.is_empty() {
+        StdOut::warn(&format!(
+            "Publishing will permanently delete every Durable Object of class {}, along with its storage",
+            deleted.join(", ")
+        ));
+    }
+
+    Ok(())
+}
+
+/// One line per class operation, in the order they are applied, paired with
+/// whether the operation destroys data.
+fn describe(api_migration: &ApiMigration) -> Vec<(String, bool)> {
+    let mut lines = Vec::new();
+
+    for (index, step) in api_migration.steps().iter().enumerate() {
+        let step = &step.durable_objects;
+        if step.is_destructive() {
+            lines.push((format!("Step {} (deletes data):", index + 1), true));
+        } else {
+            lines.push((format!("Step {}:", index + 1), false));
+        }
+        for class in &step.new_classes {
+            lines.push((format!("  + new class {}", class), false));
+        }
+        for class in &step.deleted_classes {
+            lines.push((format!("  - delete class {}", class), true));
+        }
+        for rename in &step.renamed_classes {
+            lines.push((
+                format!("  ~ rename class {} to {}", rename.from, rename.to),
+                false,
+            ));
+        }
+        for transfer in &step.transferred_classes {
+            lines.push((
+                format!(
+                    "  > transfer class {} from script {} to {}",
+                    transfer.from, transfer.from_script, transfer.to
+                ),
+                false,
+            ));
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::settings::toml::migrations::{
+        DurableObjectsMigration, Migration, MigrationConfig, RenameClass,
+    };
+
+    fn migration(tag: &str, durable_objects: DurableObjectsMigration) -> MigrationConfig {
+        MigrationConfig {
+            tag: tag.to_string(),
+            migration: Migration { durable_objects },
+        }
+    }
+
+    #[test]
+    fn it_describes_pending_steps_and_highlights_deletions() {
+        let migrations = Migrations::List {
+            script_tag: MigrationTag::HasTag("v1".to_string()),
+            migrations: vec![
+                migration(
+                    "v1",
+                    DurableObjectsMigration {
+                        new_classes: vec!["Counter".to_string()],
+                        ..Default::default()
+                    },
+                ),
+                migration(
+                    "v2",
+                    DurableObjectsMigration {
+                        renamed_classes: vec![RenameClass {
+                            from: "Counter".to_string(),
+                            to: "Tally".to_string(),
+                        }],
+                        ..Default::default()
+                    },
+                ),
+                migration(
+                    "v3",
+                    DurableObjectsMigration {
+                        deleted_classes: vec!["Tally".to_string()],
+                        ..Default::default()
+                    },
+                ),
+            ],
+        };
+        let api_migration = migrations.api_migration().unwrap().unwrap();
+
+        assert_eq!(api_migration.old_tag(), Some("v1"));
+        assert_eq!(api_migration.new_tag(), Some("v3"));
+        assert_eq!(
+            describe(&api_migration),
+            vec![
+                ("Step 1:".to_string(), false),
+                ("  ~ rename class Counter to Tally".to_string(), false),
+                ("Step 2 (deletes data):".to_string(), true),
+                ("  - delete class Tally".to_string(), true),
+            ]
+        );
+    }
+}
@@ -1,12 +1,15 @@
 use std::process::Command;
 
 pub mod config;
+pub mod deployments;
 pub mod dev;
+pub mod diff;
 pub mod generate;
 pub mod init;
 pub mod kv;
 pub mod login;
 pub mod logout;
+pub mod migrations;
 mod preview;
 pub mod publish;
 pub mod r2;
@@ -20,7 +23,7 @@ pub use self::config::global_config;
 pub use self::preview::run as preview;
 pub use generate::generate;
 pub use init::init;
-pub use publish::publish;
+pub use publish::{publish, PublishOptions};
 pub use whoami::whoami;
 
 use anyhow::Result;
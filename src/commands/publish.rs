@@ -1,29 +1,103 @@
 use std::env;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
+use atty::Stream;
 use indicatif::{ProgressBar, ProgressStyle};
 use reqwest::blocking::Client;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::build::build_target;
-use crate::deploy::{self, DeploymentSet};
+use crate::commands::deployments::history as deployment_history;
+use crate::commands::diff;
+use crate::commands::migrations::history;
+use crate::commands::secret;
+use crate::deploy::{self, DeployTarget, DeploymentSet};
 use crate::http::{self, Feature};
 use crate::kv::bulk;
+use crate::settings::get_wrangler_home_dir;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::migrations::{MigrationTag, Migrations};
-use crate::settings::toml::Target;
+use crate::settings::toml::{Manifest, Target, TargetType};
 use crate::sites;
-use crate::terminal::emoji;
 use crate::terminal::message::{Message, Output, StdErr, StdOut};
+use crate::terminal::{emoji, interactive};
 use crate::upload;
+use crate::upload::form::{FormPart, PartContent};
+use crate::ApiError;
 
-#[derive(Serialize, Deserialize, Default)]
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct PublishOutput {
     pub success: bool,
     pub name: String,
     pub urls: Vec<String>,
     pub schedules: Vec<String>,
+    /// The bindings the script was uploaded with, as the API received them
+    pub bindings: Vec<Value>,
+    pub compatibility_date: Option<String>,
+    pub compatibility_flags: Vec<String>,
+    pub usage_model: Option<String>,
+    /// Only set when the configuration has Durable Object migrations
+    pub migration_tag: Option<MigrationTagChange>,
+    /// In bytes, over every part of the upload form but its metadata
+    pub script_size: u64,
+    /// Only set for Workers Sites
+    pub site_assets: Option<SiteAssetsOutput>,
+    /// Only set when publishing with `--diff`
+    pub changes: Vec<diff::Change>,
+    pub error: Option<PublishError>,
+}
+
+impl PublishOutput {
+    pub fn failed(name: String, error: &anyhow::Error) -> PublishOutput {
+        PublishOutput {
+            success: false,
+            name,
+            error: Some(PublishError::new(error)),
+            ..Default::default()
+        }
+    }
+}
+
+/// The script's migration tag before and after publishing.
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq)]
+pub struct MigrationTagChange {
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Default, PartialEq)]
+pub struct SiteAssetsOutput {
+    pub uploaded: usize,
+    pub deleted: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq)]
+pub struct PublishError {
+    pub message: String,
+    /// The codes of the API errors publishing failed with, if any
+    pub codes: Vec<u16>,
+}
+
+impl PublishError {
+    pub fn new(error: &anyhow::Error) -> PublishError {
+        PublishError {
+            message: format!("{:#}", error),
+            codes: ApiError::codes_of(error),
+        }
+    }
+}
+
+/// Options changing how `publish` goes about publishing.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PublishOptions {
+    /// Show what publishing changes, and ask before going ahead when interactive
+    pub diff: bool,
+    /// Delete routes that point to the script but aren't configured anymore
+    pub prune_routes: bool,
+    /// Reuse the build of an earlier publish with the same build configuration
+    pub skip_build: bool,
 }
 
 pub fn publish(
@@ -31,43 +105,67 @@ pub fn publish(
     target: &mut Target,
     deployments: DeploymentSet,
     out: Output,
-) -> Result<()> {
+    options: PublishOptions,
+) -> Result<Option<PublishOutput>> {
     validate_target_required_fields_present(target)?;
 
-    let run_deploy = |target: &Target| match deploy::deploy(user, &deployments) {
-        Ok(results) => {
-            build_output_message(results, target.name.clone(), out);
-            Ok(())
+    let run_deploy = |target: &Target,
+                      parts: &[FormPart],
+                      site_assets: Option<SiteAssetsOutput>|
+     -> Result<PublishOutput> {
+        let results = deploy::deploy(user, &deployments)?;
+        let output = build_output_message(results, target, parts, site_assets)?;
+        if options.prune_routes {
+            prune_routes(user, target, &deployments)?;
         }
-        Err(e) => Err(e),
+        Ok(output)
     };
 
     // Build the script before uploading and log build result
-    let build_result = build_target(target);
-    match build_result {
-        Ok(msg) => {
-            StdErr::success(&msg);
-            Ok(())
-        }
-        Err(e) => Err(e),
-    }?;
+    if options.skip_build {
+        StdErr::info("Skipping the build, which has the same configuration as the last one");
+    } else {
+        let build_result = build_target(target);
+        match build_result {
+            Ok(msg) => {
+                StdErr::success(&msg);
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }?;
+    }
 
     if let Some(build_config) = &target.build {
         build_config.verify_upload_dir()?;
     }
 
+    // Prompting would corrupt JSON output, so only prompt for missing secrets otherwise
+    secret::verify_secrets(user, target, out != Output::Json)?;
+
+    if !target.services.is_empty() {
+        let client = http::legacy_auth_client(user);
+        validate_services(&client, target)?;
+    }
+
     if target.migrations.is_some() {
         // Can't do this in the if below, since that one takes a mutable borrow on target
         let client = http::legacy_auth_client(user);
-        let script_migration_tag = get_migration_tag(&client, target)?;
-
-        match target.migrations.as_mut().unwrap() {
-            Migrations::Adhoc { script_tag, .. } => *script_tag = script_migration_tag,
-            Migrations::List { script_tag, .. } => *script_tag = script_migration_tag,
-        };
+        resolve_migrations(&client, target)?;
     }
 
-    if let Some(site_config) = &target.site {
+    let changes = if options.diff {
+        match confirm_diff(user, target, &deployments, out, options)? {
+            Some(changes) => changes,
+            None => {
+                StdErr::info("Publish cancelled; nothing was changed");
+                return Ok(None);
+            }
+        }
+    } else {
+        Vec::new()
+    };
+
+    let output = if let Some(site_config) = &target.site {
         let path = &site_config.bucket.clone();
         validate_bucket_location(path)?;
 
@@ -75,6 +173,10 @@ pub fn publish(
 
         let (to_upload, to_delete, asset_manifest) =
             sites::sync(target, user, &site_namespace.id, path)?;
+        let site_assets = SiteAssetsOutput {
+            uploaded: to_upload.len(),
+            deleted: to_delete.len(),
+        };
 
         // First, upload all existing files in bucket directory
         StdErr::working("Uploading site files");
@@ -102,9 +204,14 @@ pub fn publish(
         let upload_client = http::featured_legacy_auth_client(user, Feature::Sites);
 
         // Next, upload and deploy the worker with the updated asset_manifest
-        upload::script(&upload_client, target, Some(asset_manifest))?;
-
-        run_deploy(target)?;
+        let parts = upload::form::build_parts(target, Some(asset_manifest), None)?;
+        let output = upload_and_deploy(
+            &get_wrangler_home_dir(),
+            target,
+            &parts,
+            || upload::script_parts(&upload_client, target, parts.clone()),
+            || run_deploy(target, &parts, Some(site_assets)),
+        )?;
 
         // Finally, remove any stale files
         if !to_delete.is_empty() {
@@ -132,17 +239,449 @@ pub fn publish(
                 pb.finish_with_message("Done deleting");
             }
         }
+
+        output
     } else {
         let upload_client = http::legacy_auth_client(user);
 
-        upload::script(&upload_client, target, None)?;
-        run_deploy(target)?;
+        let parts = upload::form::build_parts(target, None, None)?;
+        upload_and_deploy(
+            &get_wrangler_home_dir(),
+            target,
+            &parts,
+            || upload::script_parts(&upload_client, target, parts.clone()),
+            || run_deploy(target, &parts, None),
+        )?
+    };
+
+    Ok(Some(PublishOutput { changes, ..output }))
+}
+
+// The script is live as soon as it's uploaded, so it's recorded then; deploying
+// it to routes failing afterwards doesn't make it any less published
+fn upload_and_deploy(
+    home: &Path,
+    target: &Target,
+    parts: &[FormPart],
+    upload: impl FnOnce() -> Result<()>,
+    deploy: impl FnOnce() -> Result<PublishOutput>,
+) -> Result<PublishOutput> {
+    upload()?;
+    history::record(target);
+    deployment_history::record(home, target, parts, None);
+
+    deploy()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvironmentStatus {
+    Published,
+    Failed,
+    Cancelled,
+    /// Not published, because an earlier environment failed or was cancelled
+    Skipped,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnvironmentOutput {
+    /// None for the top level of the configuration file
+    pub environment: Option<String>,
+    pub status: EnvironmentStatus,
+    #[serde(flatten)]
+    pub output: PublishOutput,
+}
+
+#[derive(Debug, Serialize)]
+struct EnvironmentsOutput {
+    success: bool,
+    environments: Vec<EnvironmentOutput>,
+}
+
+/// Publishes several environments in order, stopping at the first one that
+/// fails. Every environment is resolved before anything is published, and
+/// environments sharing a build configuration are only built once. The
+/// summary reports every failure, including failing to resolve an environment.
+pub fn publish_environments(
+    user: &GlobalUser,
+    manifest: &Manifest,
+    environments: &[Option<String>],
+    out: Output,
+    options: PublishOptions,
+) -> Result<()> {
+    let planned: Vec<_> = environments
+        .iter()
+        .map(|environment| {
+            let resolved = manifest
+                .get_target(environment.as_deref(), false)
+                .and_then(|target| {
+                    let deployments = manifest.get_deployments(environment.as_deref())?;
+                    Ok((target, deployments))
+                });
+            (environment.clone(), resolved)
+        })
+        .collect();
+
+    let (summary, failure) = publish_planned(planned, options, |target, deployments, options| {
+        publish(user, target, deployments, out, options)
+    });
+
+    if out == Output::Json {
+        StdOut::as_json(&EnvironmentsOutput {
+            success: failure.is_none(),
+            environments: summary,
+        });
+    } else {
+        let mut msg = "Environments:".to_string();
+        for result in &summary {
+            msg.push_str(&format!(
+                "\n {} ({}) => {}",
+                describe_environment(&result.environment),
+                result.output.name,
+                match result.status {
+                    EnvironmentStatus::Published => "published",
+                    EnvironmentStatus::Failed => "failed",
+                    EnvironmentStatus::Cancelled => "cancelled",
+                    EnvironmentStatus::Skipped => "skipped",
+                }
+            ));
+        }
+        StdErr::info(&msg);
+    }
+
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+type PlannedEnvironment = (Option<String>, Result<(Target, DeploymentSet)>);
+
+// Publishes the environments with `publish_one` until one of them fails or is
+// cancelled, returning the summary of every environment along with the failure
+fn publish_planned(
+    planned: Vec<PlannedEnvironment>,
+    options: PublishOptions,
+    mut publish_one: impl FnMut(
+        &mut Target,
+        DeploymentSet,
+        PublishOptions,
+    ) -> Result<Option<PublishOutput>>,
+) -> (Vec<EnvironmentOutput>, Option<anyhow::Error>) {
+    // why environments after a failure aren't published
+    let mut failure = None;
+    let mut skip_reason = String::new();
+    if let Some((environment, Err(e))) = planned.iter().find(|(_, resolved)| resolved.is_err()) {
+        failure = Some(anyhow::anyhow!(
+            "Could not resolve {}: {}",
+            describe_environment(environment),
+            e
+        ));
+        skip_reason = format!(
+            "Not published, because {} could not be resolved",
+            describe_environment(environment)
+        );
+    }
+
+    let mut built: Vec<Target> = Vec::new();
+    let mut summary: Vec<EnvironmentOutput> = Vec::new();
+    for (environment, resolved) in planned {
+        let (mut target, deployments) = match resolved {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                summary.push(EnvironmentOutput {
+                    environment,
+                    status: EnvironmentStatus::Failed,
+                    output: PublishOutput::failed(String::new(), &e),
+                });
+                continue;
+            }
+        };
+        let mut result = EnvironmentOutput {
+            environment: environment.clone(),
+            status: EnvironmentStatus::Skipped,
+            output: PublishOutput {
+                name: target.name.clone(),
+                ..Default::default()
+            },
+        };
+        if failure.is_some() {
+            result.output.error = Some(PublishError {
+                message: skip_reason.clone(),
+                codes: Vec::new(),
+            });
+            summary.push(result);
+            continue;
+        }
+
+        StdErr::working(&format!(
+            "Publishing {}",
+            describe_environment(&environment)
+        ));
+        let options = PublishOptions {
+            skip_build: built.iter().any(|b| same_build(b, &target)),
+            ..options
+        };
+        match publish_one(&mut target, deployments, options) {
+            Ok(Some(output)) => {
+                result.status = EnvironmentStatus::Published;
+                result.output = output;
+                built.push(target);
+            }
+            Ok(None) => {
+                let cancelled = format!(
+                    "Publishing {} was cancelled",
+                    describe_environment(&environment)
+                );
+                result.status = EnvironmentStatus::Cancelled;
+                result.output.error = Some(PublishError {
+                    message: cancelled.clone(),
+                    codes: Vec::new(),
+                });
+                failure = Some(anyhow::anyhow!(cancelled));
+                skip_reason = format!(
+                    "Not published, because publishing {} was cancelled",
+                    describe_environment(&environment)
+                );
+            }
+            Err(e) => {
+                result.status = EnvironmentStatus::Failed;
+                result.output = PublishOutput::failed(target.name.clone(), &e);
+                failure = Some(anyhow::anyhow!(
+                    "Publishing {} failed: {}",
+                    describe_environment(&environment),
+                    e
+                ));
+                skip_reason = format!(
+                    "Not published, because publishing {} failed",
+                    describe_environment(&environment)
+                );
+            }
+        }
+        summary.push(result);
+    }
+
+    (summary, failure)
+}
+
+fn describe_environment(environment: &Option<String>) -> String {
+    match environment {
+        Some(environment) => format!("environment {}", environment),
+        None => "the top level configuration".to_string(),
+    }
+}
+
+// webpack builds depend on more of the target than its build configuration
+fn same_build(built: &Target, target: &Target) -> bool {
+    built.target_type == target.target_type
+        && built.target_type != TargetType::Webpack
+        && built.build == target.build
+}
+
+#[derive(Serialize)]
+struct DryRunPart {
+    name: String,
+    content_type: Option<String>,
+    size: u64,
+}
+
+#[derive(Serialize)]
+struct DryRunOutput {
+    name: String,
+    parts: Vec<DryRunPart>,
+    /// The metadata part, which describes every binding
+    metadata: Option<Value>,
+    outdir: Option<PathBuf>,
+}
+
+/// Builds the target and its upload form without uploading or deploying
+/// anything, optionally writing each part of the form to `outdir`. Credentials
+/// are only needed to look up the script's migration tag, so configurations
+/// without migrations can be checked offline.
+pub fn dry_run(target: &mut Target, outdir: Option<&Path>, out: Output) -> Result<()> {
+    validate_target_required_fields_present(target)?;
+
+    let msg = build_target(target)?;
+    StdErr::success(&msg);
+
+    if let Some(build_config) = &target.build {
+        build_config.verify_upload_dir()?;
+    }
+
+    if target.migrations.is_some() {
+        let user = GlobalUser::new()?;
+        let client = http::legacy_auth_client(&user);
+        resolve_migrations(&client, target)?;
+    }
+
+    let asset_manifest = match &target.site {
+        Some(site_config) => {
+            let path = site_config.bucket.clone();
+            validate_bucket_location(&path)?;
+            let (_, asset_manifest, _) = sites::directory_keys_values(target, &path, None)?;
+            StdErr::info(
+                "The binding to your site's Workers KV namespace is only added when publishing",
+            );
+            Some(asset_manifest)
+        }
+        None => None,
+    };
+
+    let parts = upload::form::build_parts(target, asset_manifest, None)?;
+    if let Some(outdir) = outdir {
+        upload::form::write_parts(&parts, outdir)?;
+    }
+
+    let metadata = match parts
+        .iter()
+        .find(|part| part.name == "metadata")
+        .map(|part| &part.content)
+    {
+        Some(PartContent::Text(metadata)) => Some(serde_json::from_str(metadata)?),
+        _ => None,
+    };
+    let summary = DryRunOutput {
+        name: target.name.clone(),
+        parts: parts
+            .iter()
+            .map(|part| {
+                Ok(DryRunPart {
+                    name: part.name.clone(),
+                    content_type: part.content_type.clone(),
+                    size: part.size()?,
+                })
+            })
+            .collect::<Result<_>>()?,
+        metadata,
+        outdir: outdir.map(Path::to_path_buf),
+    };
+
+    if out == Output::Json {
+        StdOut::as_json(&summary);
+        return Ok(());
+    }
+
+    let mut msg = format!(
+        "Dry run of {}; nothing was uploaded or deployed. The upload form has these parts:",
+        summary.name
+    );
+    for part in &summary.parts {
+        msg.push_str(&format!(
+            "\n {} ({}, {} bytes)",
+            part.name,
+            part.content_type
+                .as_deref()
+                .unwrap_or("detected from extension"),
+            part.size
+        ));
+    }
+    StdOut::info(&msg);
+
+    match outdir {
+        Some(outdir) => StdOut::success(&format!("Wrote the upload form to {}", outdir.display())),
+        None => {
+            // the metadata describes every binding, so show it in full
+            if let Some(metadata) = &summary.metadata {
+                StdOut::message(&serde_json::to_string_pretty(metadata)?);
+            }
+        }
     }
 
     Ok(())
 }
 
-fn build_output_message(deploy_results: deploy::DeployResults, target_name: String, out: Output) {
+// Shows the changes publishing would make, and returns them unless publishing
+// was cancelled
+fn confirm_diff(
+    user: &GlobalUser,
+    target: &Target,
+    deployments: &DeploymentSet,
+    out: Output,
+    options: PublishOptions,
+) -> Result<Option<Vec<diff::Change>>> {
+    let changes = diff::diff(user, target, deployments, options.prune_routes)?;
+    if changes.is_empty() {
+        StdErr::info(&format!(
+            "Publishing only updates the code of {}; its bindings, settings, routes and schedules are unchanged",
+            target.name
+        ));
+        return Ok(Some(changes));
+    }
+
+    let mut msg = format!("Publishing {} will make these changes:", target.name);
+    for change in &changes {
+        msg.push_str(&format!("\n {}", change));
+    }
+    StdErr::info(&msg);
+
+    // Prompting would corrupt JSON output, like it would for missing secrets
+    if out == Output::Json || !atty::is(Stream::Stdin) {
+        return Ok(Some(changes));
+    }
+    Ok(interactive::confirm("Publish these changes?")?.then(|| changes))
+}
+
+// Only zones the configuration still has routes in are pruned
+fn prune_routes(user: &GlobalUser, target: &Target, deployments: &DeploymentSet) -> Result<()> {
+    let zones: Vec<_> = deployments
+        .iter()
+        .filter_map(|deployment| match deployment {
+            DeployTarget::Zoned(zoned) => Some(zoned),
+            _ => None,
+        })
+        .collect();
+    // which zones the script used to have routes in isn't recorded anywhere
+    if zones.is_empty() {
+        StdErr::warn("Your configuration file has no routes, so no zones were pruned; delete stale routes with `wrangler route delete`");
+        return Ok(());
+    }
+    let names: Vec<String> = zones.iter().map(|zoned| zoned.zone.to_string()).collect();
+    StdErr::info(&format!(
+        "Pruning routes in {}; zones without routes in your configuration file aren't pruned",
+        names.join(", ")
+    ));
+
+    let mut pruned = Vec::new();
+    let result = zones
+        .iter()
+        .try_for_each(|zoned| zoned.prune(user, &target.name, &mut pruned));
+
+    // routes deleted before a failure are gone all the same, so list them either way
+    if pruned.is_empty() {
+        if result.is_ok() {
+            StdErr::info("No stale routes to prune");
+        }
+    } else {
+        let patterns: Vec<&str> = pruned.iter().map(|route| route.pattern.as_str()).collect();
+        StdErr::success(&format!(
+            "Deleted these routes, which pointed to {} but aren't in your configuration file:\n {}",
+            target.name,
+            patterns.join("\n ")
+        ));
+    }
+
+    result
+}
+
+// Sets the script's current migration tag, which decides the migrations to send
+fn resolve_migrations(client: &Client, target: &mut Target) -> Result<()> {
+    let script_migration_tag = get_migration_tag(client, target)?;
+
+    match target.migrations.as_mut().unwrap() {
+        Migrations::Adhoc { script_tag, .. } => *script_tag = script_migration_tag,
+        Migrations::List { script_tag, .. } => *script_tag = script_migration_tag,
+    };
+
+    let applied = history::load(target)?;
+    target.migrations.as_ref().unwrap().verify_history(&applied)
+}
+
+fn build_output_message(
+    deploy_results: deploy::DeployResults,
+    target: &Target,
+    parts: &[FormPart],
+    site_assets: Option<SiteAssetsOutput>,
+) -> Result<PublishOutput> {
     let deploy::DeployResults { urls, schedules } = deploy_results;
 
     let mut msg = "Successfully published your script ".to_owned();
@@ -154,14 +693,80 @@ fn build_output_message(deploy_results: deploy::DeployResults, target_name: Stri
     }
 
     StdErr::success(&msg);
-    if out == Output::Json {
-        StdOut::as_json(&PublishOutput {
-            success: true,
-            name: target_name,
-            urls,
-            schedules,
-        });
-    }
+    let mut output = PublishOutput {
+        success: true,
+        name: target.name.clone(),
+        urls,
+        schedules,
+        site_assets,
+        ..Default::default()
+    };
+    describe_upload(&mut output, target, parts)?;
+
+    Ok(output)
+}
+
+// The upload form's metadata has the bindings and settings exactly as the API
+// received them, so describe the upload from it rather than from the target
+fn describe_upload(output: &mut PublishOutput, target: &Target, parts: &[FormPart]) -> Result<()> {
+    let metadata: Value = match parts
+        .iter()
+        .find(|part| part.name == "metadata")
+        .map(|part| &part.content)
+    {
+        Some(PartContent::Text(metadata)) => serde_json::from_str(metadata)?,
+        _ => Value::Null,
+    };
+    let string = |key: &str| {
+        metadata
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+
+    output.bindings = metadata
+        .get("bindings")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    output.compatibility_date = string("compatibility_date");
+    output.compatibility_flags = metadata
+        .get("compatibility_flags")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(str::to_string)
+        .collect();
+    output.usage_model = string("usage_model");
+
+    output.migration_tag = target.migrations.as_ref().map(|migrations| {
+        let before = match migrations {
+            Migrations::Adhoc { script_tag, .. } | Migrations::List { script_tag, .. } => {
+                match script_tag {
+                    MigrationTag::HasTag(tag) => Some(tag.clone()),
+                    _ => None,
+                }
+            }
+        };
+        // without migrations to apply, the tag stays as it was
+        let after = match metadata.get("migrations") {
+            Some(migrations) if !migrations.is_null() => migrations
+                .get("new_tag")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            _ => before.clone(),
+        };
+        MigrationTagChange { before, after }
+    });
+
+    output.script_size = parts
+        .iter()
+        .filter(|part| part.name != "metadata")
+        .map(FormPart::size)
+        .sum::<Result<u64>>()?;
+
+    Ok(())
 }
 
 // We don't want folks setting their bucket to the top level directory,
@@ -220,6 +825,16 @@ fn validate_target_required_fields_present(target: &Target) -> Result<()> {
         }
     }
 
+    for service in &target.services {
+        if service.binding.is_empty() {
+            missing_fields.push("service binding")
+        }
+
+        if service.service.is_empty() {
+            missing_fields.push("service service")
+        }
+    }
+
     let (field_pluralization, is_are) = match missing_fields.len() {
         n if n >= 2 => ("fields", "are"),
         1 => ("field", "is"),
@@ -239,21 +854,15 @@ fn validate_target_required_fields_present(target: &Target) -> Result<()> {
     Ok(())
 }
 
-fn get_migration_tag(client: &Client, target: &Target) -> Result<MigrationTag, anyhow::Error> {
+pub fn get_migration_tag(client: &Client, target: &Target) -> Result<MigrationTag, anyhow::Error> {
     // Today, the easiest way to get metadata about a script (including the migration tag)
     // is the list endpoint, as the individual script endpoint just returns the source code for a
     // given script (and doesn't work at all for DOs). Once we add an individual script metadata
     // endpoint, we could use that here instead of listing all of the scripts. Listing isn't too bad
     // today though, as most accounts are limited to 30 scripts anyways.
+    let scripts = list_scripts(client, target)?;
 
-    let addr = format!(
-        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts",
-        target.account_id.load()?
-    );
-
-    let res: ListScriptsV4ApiResponse = client.get(&addr).send()?.json()?;
-
-    let tag = match res.result.into_iter().find(|s| s.id == target.name) {
+    let tag = match scripts.into_iter().find(|s| s.id == target.name) {
         Some(ScriptResponse {
             migration_tag: Some(tag),
             ..
@@ -270,6 +879,46 @@ fn get_migration_tag(client: &Client, target: &Target) -> Result<MigrationTag, a
     Ok(tag)
 }
 
+// Service bindings to a script that doesn't exist are rejected by the API with an
+// unhelpful error, so check for them before uploading anything.
+fn validate_services(client: &Client, target: &Target) -> Result<()> {
+    let scripts = list_scripts(client, target)?;
+    let missing = missing_services(target, &scripts);
+
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "{} Your configuration file binds to services that don't exist on this account: {}. Publish them before publishing {}.",
+            emoji::WARN,
+            missing.join(", "),
+            target.name
+        )
+    }
+
+    Ok(())
+}
+
+fn missing_services(target: &Target, scripts: &[ScriptResponse]) -> Vec<String> {
+    target
+        .services
+        .iter()
+        // a worker can bind to itself, even before it is first published
+        .filter(|service| service.service != target.name)
+        .filter(|service| !scripts.iter().any(|s| s.id == service.service))
+        .map(|service| format!("{} (bound as {})", service.service, service.binding))
+        .collect()
+}
+
+fn list_scripts(client: &Client, target: &Target) -> Result<Vec<ScriptResponse>> {
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts",
+        target.account_id.load()?
+    );
+
+    let res: ListScriptsV4ApiResponse = http::send(client.get(&addr))?.json()?;
+
+    Ok(res.result)
+}
+
 #[derive(Debug, Deserialize)]
 struct ListScriptsV4ApiResponse {
     pub result: Vec<ScriptResponse>,
@@ -280,3 +929,284 @@ struct ScriptResponse {
     pub id: String,
     pub migration_tag: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::settings::toml::ServiceBinding;
+
+    fn service(binding: &str, service: &str) -> ServiceBinding {
+        ServiceBinding {
+            binding: binding.to_string(),
+            service: service.to_string(),
+            environment: None,
+        }
+    }
+
+    fn script(id: &str) -> ScriptResponse {
+        ScriptResponse {
+            id: id.to_string(),
+            migration_tag: None,
+        }
+    }
+
+    #[test]
+    fn it_finds_services_that_do_not_exist() {
+        let target = Target {
+            name: "gateway".to_string(),
+            services: vec![
+                service("AUTH", "auth-worker"),
+                service("BILLING", "billing-worker"),
+                service("SELF", "gateway"),
+            ],
+            ..Default::default()
+        };
+
+        let missing = missing_services(&target, &[script("auth-worker")]);
+
+        assert_eq!(missing, vec!["billing-worker (bound as BILLING)"]);
+    }
+
+    #[test]
+    fn it_reuses_builds_with_the_same_configuration() {
+        let staging = Target {
+            name: "worker-staging".to_string(),
+            target_type: TargetType::JavaScript,
+            ..Default::default()
+        };
+        let production = Target {
+            name: "worker-production".to_string(),
+            ..staging.clone()
+        };
+        let rust = Target {
+            target_type: TargetType::Rust,
+            ..staging.clone()
+        };
+        let webpack = Target {
+            target_type: TargetType::Webpack,
+            ..Default::default()
+        };
+
+        assert!(same_build(&staging, &production));
+        assert!(!same_build(&staging, &rust));
+        assert!(!same_build(&webpack, &webpack));
+    }
+
+    fn planned(environments: &[&str]) -> Vec<PlannedEnvironment> {
+        environments
+            .iter()
+            .map(|environment| {
+                let target = Target {
+                    name: format!("worker-{}", environment),
+                    ..Default::default()
+                };
+                (Some(environment.to_string()), Ok((target, Vec::new())))
+            })
+            .collect()
+    }
+
+    fn statuses(summary: &[EnvironmentOutput]) -> Vec<EnvironmentStatus> {
+        summary.iter().map(|result| result.status).collect()
+    }
+
+    fn published(target: &Target) -> Result<Option<PublishOutput>> {
+        Ok(Some(PublishOutput {
+            success: true,
+            name: target.name.clone(),
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn it_skips_environments_after_a_failure() {
+        let mut attempted = Vec::new();
+        let (summary, failure) = publish_planned(
+            planned(&["a", "b", "c"]),
+            PublishOptions::default(),
+            |target, _, _| {
+                attempted.push(target.name.clone());
+                if target.name == "worker-b" {
+                    anyhow::bail!("upload failed")
+                }
+                published(target)
+            },
+        );
+
+        assert_eq!(attempted, vec!["worker-a", "worker-b"]);
+        assert_eq!(
+            statuses(&summary),
+            vec![
+                EnvironmentStatus::Published,
+                EnvironmentStatus::Failed,
+                EnvironmentStatus::Skipped,
+            ]
+        );
+        assert!(failure
+            .unwrap()
+            .to_string()
+            .contains("environment b failed"));
+
+        let json = serde_json::to_value(&EnvironmentsOutput {
+            success: false,
+            environments: summary,
+        })
+        .unwrap();
+        assert_eq!(json["environments"][0]["environment"], "a");
+        assert_eq!(json["environments"][0]["success"], true);
+        assert_eq!(json["environments"][1]["status"], "failed");
+        assert!(json["environments"][1]["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("upload failed"));
+        assert_eq!(json["environments"][2]["name"], "worker-c");
+        assert_eq!(json["environments"][2]["status"], "skipped");
+        assert_eq!(
+            json["environments"][2]["error"]["message"],
+            "Not published, because publishing environment b failed"
+        );
+    }
+
+    #[test]
+    fn it_skips_environments_after_a_cancellation() {
+        let (summary, failure) = publish_planned(
+            planned(&["a", "b"]),
+            PublishOptions::default(),
+            |_, _, _| Ok(None),
+        );
+
+        assert_eq!(
+            statuses(&summary),
+            vec![EnvironmentStatus::Cancelled, EnvironmentStatus::Skipped]
+        );
+        assert!(failure.is_some());
+        assert_eq!(
+            summary[1].output.error.as_ref().unwrap().message,
+            "Not published, because publishing environment a was cancelled"
+        );
+    }
+
+    #[test]
+    fn it_publishes_nothing_when_an_environment_does_not_resolve() {
+        let mut planned = planned(&["a", "b", "c"]);
+        planned[1].1 = Err(anyhow::anyhow!("no such environment"));
+
+        let mut attempted = 0;
+        let (summary, failure) =
+            publish_planned(planned, PublishOptions::default(), |target, _, _| {
+                attempted += 1;
+                published(target)
+            });
+
+        assert_eq!(attempted, 0);
+        assert_eq!(
+            statuses(&summary),
+            vec![
+                EnvironmentStatus::Skipped,
+                EnvironmentStatus::Failed,
+                EnvironmentStatus::Skipped,
+            ]
+        );
+        assert!(failure
+            .unwrap()
+            .to_string()
+            .contains("Could not resolve environment b"));
+        assert_eq!(
+            summary[0].output.error.as_ref().unwrap().message,
+            "Not published, because environment b could not be resolved"
+        );
+    }
+
+    #[test]
+    fn it_records_uploads_whose_deploy_fails() {
+        let home = tempfile::tempdir().unwrap();
+        let target = Target {
+            name: "worker".to_string(),
+            account_id: Some("account".to_string()).into(),
+            ..Default::default()
+        };
+        let parts = vec![FormPart::text(
+            "metadata",
+            "metadata.json",
+            "application/json",
+            "{}".to_string(),
+        )];
+
+        let failed_upload = upload_and_deploy(
+            home.path(),
+            &target,
+            &parts,
+            || Err(anyhow::anyhow!("upload failed")),
+            || Ok(PublishOutput::default()),
+        );
+        assert!(failed_upload.is_err());
+        assert!(deployment_history::load(home.path(), &target)
+            .unwrap()
+            .is_empty());
+
+        let failed_deploy = upload_and_deploy(
+            home.path(),
+            &target,
+            &parts,
+            || Ok(()),
+            || Err(anyhow::anyhow!("route creation failed")),
+        );
+        assert!(failed_deploy.is_err());
+        let recorded = deployment_history::load(home.path(), &target).unwrap();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].rollback_of, None);
+    }
+
+    #[test]
+    fn it_describes_the_upload_from_its_metadata() {
+        let target = Target {
+            name: "worker".to_string(),
+            migrations: Some(Migrations::List {
+                script_tag: MigrationTag::HasTag("v1".to_string()),
+                migrations: Vec::new(),
+            }),
+            ..Default::default()
+        };
+        let metadata = serde_json::json!({
+            "body_part": "script",
+            "bindings": [{"name": "GREETING", "type": "plain_text", "text": "hello"}],
+            "usage_model": "bundled",
+            "compatibility_date": "2022-01-01",
+            "compatibility_flags": ["formdata_parser_supports_files"],
+            "migrations": {"old_tag": "v1", "new_tag": "v2", "steps": []},
+        });
+        let parts = vec![
+            FormPart::text(
+                "metadata",
+                "metadata.json",
+                "application/json",
+                metadata.to_string(),
+            ),
+            FormPart::text(
+                "script",
+                "script.js",
+                "application/javascript",
+                "addEventListener()".to_string(),
+            ),
+        ];
+
+        let mut output = PublishOutput::default();
+        describe_upload(&mut output, &target, &parts).unwrap();
+
+        assert_eq!(output.bindings, vec![metadata["bindings"][0].clone()]);
+        assert_eq!(output.compatibility_date.as_deref(), Some("2022-01-01"));
+        assert_eq!(
+            output.compatibility_flags,
+            vec!["formdata_parser_supports_files"]
+        );
+        assert_eq!(output.usage_model.as_deref(), Some("bundled"));
+        assert_eq!(
+            output.migration_tag,
+            Some(MigrationTagChange {
+                before: Some("v1".to_string()),
+                after: Some("v2".to_string()),
+            })
+        );
+        assert_eq!(output.script_size, 18);
+    }
+}
@@ -1,30 +1,59 @@
 use anyhow::Result;
-use cloudflare::endpoints::workers::{DeleteRoute, ListRoutes};
+use cloudflare::endpoints::workers::{DeleteRoute, ListRoutes, WorkersRoute};
 use cloudflare::framework::apiclient::ApiClient;
 
 use crate::http;
 use crate::settings::global_user::GlobalUser;
 use crate::terminal::message::{Message, StdOut};
 
-pub fn list(zone_identifier: &str, user: &GlobalUser) -> Result<()> {
+/// Prints the routes of every zone as a single JSON array.
+pub fn list(zone_identifiers: &[String], user: &GlobalUser) -> Result<()> {
     let client = http::cf_v4_client(user)?;
 
-    let result = client.request(&ListRoutes { zone_identifier });
+    let mut routes = Vec::new();
+    for zone_identifier in zone_identifiers {
+        routes.extend(list_zone(&client, zone_identifier)?);
+    }
+    println!("{}", serde_json::to_string(&routes)?);
 
-    match result {
-        Ok(success) => {
-            let routes = success.result;
-            println!("{}", serde_json::to_string(&routes)?);
-        }
+    Ok(())
+}
 
+fn list_zone(client: &impl ApiClient, zone_identifier: &str) -> Result<Vec<WorkersRoute>> {
+    match client.request(&ListRoutes { zone_identifier }) {
+        Ok(success) => Ok(success.result),
         Err(e) => anyhow::bail!("{}", http::format_error(e, None)),
     }
-    Ok(())
 }
 
-pub fn delete(zone_identifier: &str, user: &GlobalUser, route_id: &str) -> Result<()> {
+pub fn delete(zone_identifiers: &[String], user: &GlobalUser, route_id: &str) -> Result<()> {
     let client = http::cf_v4_client(user)?;
 
+    // with several zones, delete from the one the route belongs to
+    let zone_identifier = match zone_identifiers {
+        [zone_identifier] => zone_identifier.as_str(),
+        _ => {
+            let mut found = None;
+            for zone_identifier in zone_identifiers {
+                if list_zone(&client, zone_identifier)?
+                    .iter()
+                    .any(|route| route.id == route_id)
+                {
+                    found = Some(zone_identifier.as_str());
+                    break;
+                }
+            }
+            match found {
+                Some(zone_identifier) => zone_identifier,
+                None => anyhow::bail!(
+                    "There is no route with id {} in the zones of your configuration file. {}",
+                    route_id,
+                    error_suggestions(10005)
+                ),
+            }
+        }
+    };
+
     let result = client.request(&DeleteRoute {
         zone_identifier,
         identifier: route_id,
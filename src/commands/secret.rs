@@ -3,12 +3,13 @@ use cloudflare::framework::apiclient::ApiClient;
 use cloudflare::framework::response::ApiFailure;
 
 use anyhow::Result;
+use atty::Stream;
 
 use crate::http;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 use crate::terminal::interactive;
-use crate::terminal::message::{Message, StdOut};
+use crate::terminal::message::{Message, StdErr, StdOut};
 use crate::upload;
 
 fn format_error(e: ApiFailure) -> String {
@@ -158,3 +159,125 @@ pub fn list_secrets(user: &GlobalUser, target: &Target) -> Result<()> {
 
     Ok(())
 }
+
+/// Check that every secret declared in the configuration file exists on the
+/// script, offering to create any that are missing when `prompt` is set.
+/// Secrets that exist remotely but are no longer declared only get a warning.
+pub fn verify_secrets(user: &GlobalUser, target: &Target, prompt: bool) -> Result<()> {
+    let declared = match &target.secrets {
+        Some(declared) => declared,
+        None => return Ok(()),
+    };
+
+    let remote = remote_secret_names(user, target)?;
+    let (missing, undeclared) = compare_secrets(declared, &remote);
+
+    if !undeclared.is_empty() {
+        StdErr::warn(&format!(
+            "The script {} has secrets that are not declared in your configuration file: {}. Run `wrangler secret delete <name>` to remove them if they are no longer used.",
+            target.name,
+            undeclared.join(", ")
+        ));
+    }
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let message = format!(
+        "The script {} is missing secrets declared in your configuration file: {}",
+        target.name,
+        missing.join(", ")
+    );
+    if !prompt || !atty::is(Stream::Stdin) {
+        anyhow::bail!(
+            "{}. Run `wrangler secret put <name>` to create them before publishing.",
+            message
+        )
+    }
+
+    StdErr::warn(&message);
+    if !interactive::confirm("Would you like to set them now?")? {
+        anyhow::bail!("Publish cancelled; missing secrets: {}", missing.join(", "))
+    }
+    for name in missing {
+        create_secret(name, user, target)?;
+    }
+
+    Ok(())
+}
+
+fn remote_secret_names(user: &GlobalUser, target: &Target) -> Result<Vec<String>> {
+    let client = http::cf_v4_client(user)?;
+
+    let response = client.request(&ListSecrets {
+        account_identifier: target.account_id.load()?,
+        script_name: &target.name,
+    });
+
+    match response {
+        Ok(success) => Ok(success
+            .result
+            .into_iter()
+            .map(|secret| secret.name)
+            .collect()),
+        // a script that hasn't been published yet has no secrets
+        Err(ApiFailure::Error(_, api_errors))
+            if api_errors.errors.iter().any(|error| error.code == 10007) =>
+        {
+            Ok(Vec::new())
+        }
+        Err(e) => anyhow::bail!(format_error(e)),
+    }
+}
+
+/// Returns the declared secrets missing from the script, and the script's
+/// secrets that aren't declared.
+fn compare_secrets<'a>(
+    declared: &'a [String],
+    remote: &'a [String],
+) -> (Vec<&'a str>, Vec<&'a str>) {
+    let missing = declared
+        .iter()
+        .filter(|name| !remote.contains(name))
+        .map(String::as_str)
+        .collect();
+    let undeclared = remote
+        .iter()
+        .filter(|name| !declared.contains(name))
+        .map(String::as_str)
+        .collect();
+
+    (missing, undeclared)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn it_finds_missing_and_undeclared_secrets() {
+        let declared = names(&["STRIPE_KEY", "SENTRY_DSN"]);
+        let remote = names(&["SENTRY_DSN", "OLD_TOKEN"]);
+
+        let (missing, undeclared) = compare_secrets(&declared, &remote);
+
+        assert_eq!(missing, vec!["STRIPE_KEY"]);
+        assert_eq!(undeclared, vec!["OLD_TOKEN"]);
+    }
+
+    #[test]
+    fn it_accepts_matching_secrets_in_any_order() {
+        let declared = names(&["A", "B"]);
+        let remote = names(&["B", "A"]);
+
+        let (missing, undeclared) = compare_secrets(&declared, &remote);
+
+        assert!(missing.is_empty());
+        assert!(undeclared.is_empty());
+    }
+}
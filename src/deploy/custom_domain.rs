@@ -0,0 +1,123 @@
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::target::LazyAccountId;
+
+use anyhow::Result;
+use serde::Deserialize;
+
+/// Hostnames served by a worker through custom domains, for which Cloudflare
+/// manages the DNS records and certificates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CustomDomainTarget {
+    pub account_id: LazyAccountId,
+    pub script_name: String,
+    pub hostnames: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CustomDomain {
+    hostname: String,
+    service: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CustomDomainsResponse {
+    result: Vec<CustomDomain>,
+}
+
+impl CustomDomainTarget {
+    pub fn deploy(&self, user: &GlobalUser) -> Result<Vec<String>> {
+        log::info!("publishing to custom domains");
+        let domains_addr = format!(
+            "https://api.cloudflare.com/client/v4/accounts/{}/workers/domains",
+            self.account_id.load()?,
+        );
+
+        let client = http::legacy_auth_client(user);
+
+        // check every hostname before attaching any, so a conflict doesn't
+        // leave the worker on some of its domains
+        log::info!("Checking custom domains...");
+        let res = http::send(client.get(&domains_addr))?;
+        let status = res.status();
+        let text = res.text()?;
+        if !status.is_success() {
+            anyhow::bail!(crate::ApiError::from_response(text))
+        }
+        let existing: CustomDomainsResponse = serde_json::from_str(&text)?;
+        let conflicts = conflicts(&self.hostnames, &existing.result, &self.script_name);
+        if !conflicts.is_empty() {
+            anyhow::bail!(
+                "The following custom domains are already attached to other workers; detach them from the dashboard or remove them from `custom_domains`:\n{}",
+                conflicts.join("\n")
+            )
+        }
+
+        let mut urls = Vec::new();
+        for hostname in &self.hostnames {
+            log::info!("Attaching {}...", hostname);
+            let res = http::send(
+                client
+                    .put(&domains_addr)
+                    .header("Content-Type", "application/json")
+                    .body(build_domain_request(hostname, &self.script_name)),
+            )?;
+
+            let status = res.status();
+            let text = res.text()?;
+            if !status.is_success() {
+                anyhow::bail!(crate::ApiError::from_response(text))
+            }
+
+            urls.push(format!("https://{}", hostname));
+        }
+
+        Ok(urls)
+    }
+}
+
+fn conflicts(hostnames: &[String], existing: &[CustomDomain], script_name: &str) -> Vec<String> {
+    existing
+        .iter()
+        .filter(|domain| hostnames.contains(&domain.hostname) && domain.service != script_name)
+        .map(|domain| format!("{} => {}", domain.hostname, domain.service))
+        .collect()
+}
+
+fn build_domain_request(hostname: &str, script_name: &str) -> String {
+    serde_json::json!({
+        "hostname": hostname,
+        "service": script_name,
+        "environment": "production",
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_hostnames_bound_to_other_scripts() {
+        let existing = vec![
+            CustomDomain {
+                hostname: "api.example.com".to_string(),
+                service: "api".to_string(),
+            },
+            CustomDomain {
+                hostname: "www.example.com".to_string(),
+                service: "site".to_string(),
+            },
+            CustomDomain {
+                hostname: "blog.example.com".to_string(),
+                service: "blog".to_string(),
+            },
+        ];
+        let hostnames = vec!["api.example.com".to_string(), "www.example.com".to_string()];
+
+        assert_eq!(
+            conflicts(&hostnames, &existing, "api"),
+            vec!["www.example.com => site".to_string()]
+        );
+    }
+}
@@ -1,14 +1,20 @@
+mod custom_domain;
+mod parallel;
 mod schedule;
 mod zoned;
 mod zoneless;
 
+use std::fmt;
+
 use anyhow::Result;
+pub use custom_domain::CustomDomainTarget;
 use indicatif::{ProgressBar, ProgressStyle};
 pub use schedule::ScheduleTarget;
-pub use zoned::ZonedTarget;
+pub use zoned::{fetch_all, stale_routes, Zone, ZonedTarget};
 pub use zoneless::ZonelessTarget;
 
 use crate::settings::global_user::GlobalUser;
+use crate::ApiError;
 
 /// A set of deploy targets.
 pub type DeploymentSet = Vec<DeployTarget>;
@@ -18,38 +24,119 @@ pub enum DeployTarget {
     Zoned(ZonedTarget),
     Zoneless(ZonelessTarget),
     Schedule(ScheduleTarget),
+    CustomDomain(CustomDomainTarget),
 }
 
+/// Deploys to every target at once, making up to
+/// `parallel::MAX_CONCURRENT_REQUESTS` requests at a time across all of them.
+/// Results are in the order of the targets, and if any target fails, every
+/// failure is reported.
 pub fn deploy(user: &GlobalUser, deploy_targets: &[DeployTarget]) -> Result<DeployResults> {
-    let style = ProgressStyle::default_spinner().template("{spinner}   {msg}");
-    let spinner = ProgressBar::new_spinner().with_style(style);
-    spinner.enable_steady_tick(20);
-    let mut results = DeployResults::default();
+    // loading an account id can prompt, which mustn't happen on several threads
     for target in deploy_targets {
         match target {
-            DeployTarget::Zoned(zoned) => {
-                spinner.set_message("Configuring routes...");
-                let route_urls = zoned.deploy(user)?;
-                results.urls.extend(route_urls);
-            }
             DeployTarget::Zoneless(zoneless) => {
-                spinner.set_message("Configuring workers.dev...");
-                let worker_dev = zoneless.deploy(user)?;
-                results.urls.push(worker_dev);
+                zoneless.account_id.load()?;
             }
-            DeployTarget::Schedule(schedule) => {
-                spinner.set_message("Configuring schedules...");
-                let schedules = schedule.deploy(user)?;
-                results.schedules.extend(schedules);
+            DeployTarget::CustomDomain(custom_domain) => {
+                custom_domain.account_id.load()?;
             }
+            DeployTarget::Zoned(_) | DeployTarget::Schedule(_) => {}
         }
     }
 
+    let style = ProgressStyle::default_spinner().template("{spinner}   {msg}");
+    let spinner = ProgressBar::new_spinner().with_style(style);
+    spinner.enable_steady_tick(20);
+    spinner.set_message(&describe(deploy_targets));
+
+    // targets run side by side, but only as many requests as the limit allows
+    let user = user.clone();
+    let limit = parallel::Limit::new(parallel::MAX_CONCURRENT_REQUESTS);
+    let outcomes = parallel::map(
+        deploy_targets.to_vec(),
+        deploy_targets.len(),
+        move |target| deploy_target(&user, &target, &limit),
+    );
+
     spinner.finish_and_clear();
 
+    let mut results = DeployResults::default();
+    let mut failures = Vec::new();
+    let mut codes = Vec::new();
+    for (target, outcome) in deploy_targets.iter().zip(outcomes) {
+        match outcome {
+            Ok(DeployOutcome::Urls(urls)) => results.urls.extend(urls),
+            Ok(DeployOutcome::Schedules(schedules)) => results.schedules.extend(schedules),
+            Err(e) => {
+                codes.extend(ApiError::codes_of(&e));
+                failures.push(format!("{}: {}", target, e));
+            }
+        }
+    }
+    if !failures.is_empty() {
+        anyhow::bail!(ApiError {
+            codes,
+            message: format!("Deploying failed:\n{}", failures.join("\n")),
+        })
+    }
+
     Ok(results)
 }
 
+enum DeployOutcome {
+    Urls(Vec<String>),
+    Schedules(Vec<String>),
+}
+
+fn deploy_target(
+    user: &GlobalUser,
+    target: &DeployTarget,
+    limit: &parallel::Limit,
+) -> Result<DeployOutcome> {
+    match target {
+        DeployTarget::Zoned(zoned) => zoned.deploy(user, limit).map(DeployOutcome::Urls),
+        DeployTarget::Zoneless(zoneless) => limit
+            .run(|| zoneless.deploy(user))
+            .map(|worker_dev| DeployOutcome::Urls(vec![worker_dev])),
+        DeployTarget::Schedule(schedule) => limit
+            .run(|| schedule.deploy(user))
+            .map(DeployOutcome::Schedules),
+        DeployTarget::CustomDomain(custom_domain) => limit
+            .run(|| custom_domain.deploy(user))
+            .map(DeployOutcome::Urls),
+    }
+}
+
+fn describe(deploy_targets: &[DeployTarget]) -> String {
+    let mut configuring: Vec<&str> = Vec::new();
+    for target in deploy_targets {
+        let kind = match target {
+            DeployTarget::Zoned(_) => "routes",
+            DeployTarget::Zoneless(_) => "workers.dev",
+            DeployTarget::Schedule(_) => "schedules",
+            DeployTarget::CustomDomain(_) => "custom domains",
+        };
+        if !configuring.contains(&kind) {
+            configuring.push(kind);
+        }
+    }
+    format!("Configuring {}...", configuring.join(", "))
+}
+
+impl fmt::Display for DeployTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeployTarget::Zoned(zoned) => write!(f, "routes in zone {}", zoned.zone),
+            DeployTarget::Zoneless(_) => write!(f, "workers.dev"),
+            DeployTarget::Schedule(_) => write!(f, "schedules"),
+            DeployTarget::CustomDomain(custom_domain) => {
+                write!(f, "custom domains {}", custom_domain.hostnames.join(", "))
+            }
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct DeployResults {
     pub urls: Vec<String>,
@@ -0,0 +1,161 @@
+use std::panic;
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::thread;
+
+/// How many requests deploying makes at once.
+pub const MAX_CONCURRENT_REQUESTS: usize = 8;
+
+/// Limits how many calls run at once across every thread sharing it, so that
+/// nested `map`s don't each make up to their own limit of requests.
+#[derive(Clone)]
+pub struct Limit(Arc<(Mutex<usize>, Condvar)>);
+
+impl Limit {
+    pub fn new(limit: usize) -> Limit {
+        Limit(Arc::new((Mutex::new(limit.max(1)), Condvar::new())))
+    }
+
+    /// Calls `f` once fewer than the limit of calls are running.
+    pub fn run<R>(&self, f: impl FnOnce() -> R) -> R {
+        let (available, released) = &*self.0;
+        let mut count = available.lock().unwrap();
+        while *count == 0 {
+            count = released.wait(count).unwrap();
+        }
+        *count -= 1;
+        drop(count);
+
+        let _permit = Permit(self);
+        f()
+    }
+}
+
+// Gives the call's place back even if it panics
+struct Permit<'a>(&'a Limit);
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        let (available, released) = &*(self.0).0;
+        // a poisoned lock still holds a valid count
+        let mut count = available.lock().unwrap_or_else(|e| e.into_inner());
+        *count += 1;
+        released.notify_one();
+    }
+}
+
+/// Calls `f` on every item, on up to `limit` threads at once, and returns the
+/// results in the order of the items.
+pub fn map<T, R, F>(items: Vec<T>, limit: usize, f: F) -> Vec<R>
+where
+    T: Send + 'static,
+    R: Send + 'static,
+    F: Fn(T) -> R + Send + Sync + 'static,
+{
+    let count = items.len();
+    if count <= 1 {
+        return items.into_iter().map(f).collect();
+    }
+
+    let queue = Arc::new(Mutex::new(items.into_iter().enumerate()));
+    let f = Arc::new(f);
+    let (sender, receiver) = mpsc::channel();
+
+    let workers: Vec<_> = (0..limit.max(1).min(count))
+        .map(|_| {
+            let queue = Arc::clone(&queue);
+            let f = Arc::clone(&f);
+            let sender = sender.clone();
+            thread::spawn(move || loop {
+                // the lock is released before calling `f`
+                let next = queue.lock().unwrap().next();
+                match next {
+                    Some((index, item)) => {
+                        if sender.send((index, f(item))).is_err() {
+                            return;
+                        }
+                    }
+                    None => return,
+                }
+            })
+        })
+        .collect();
+    drop(sender);
+
+    let mut results: Vec<Option<R>> = (0..count).map(|_| None).collect();
+    for (index, result) in receiver {
+        results[index] = Some(result);
+    }
+    for worker in workers {
+        if let Err(e) = worker.join() {
+            panic::resume_unwind(e);
+        }
+    }
+
+    results
+        .into_iter()
+        .map(|result| result.expect("every item has a result"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[test]
+    fn it_keeps_the_order_of_the_items() {
+        let items: Vec<u64> = (0..20).collect();
+
+        let results = map(items, 4, |item| {
+            // finish out of order
+            thread::sleep(Duration::from_millis((20 - item) % 7));
+            item * 2
+        });
+
+        assert_eq!(results, (0..20).map(|item| item * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn it_bounds_the_number_of_threads() {
+        let running = Arc::new(Mutex::new((0, 0)));
+
+        let tracked = Arc::clone(&running);
+        map((0..12).collect::<Vec<u32>>(), 3, move |_| {
+            {
+                let mut running = tracked.lock().unwrap();
+                running.0 += 1;
+                running.1 = running.1.max(running.0);
+            }
+            thread::sleep(Duration::from_millis(5));
+            tracked.lock().unwrap().0 -= 1;
+        });
+
+        assert!(running.lock().unwrap().1 <= 3);
+    }
+
+    #[test]
+    fn it_shares_a_limit_between_nested_maps() {
+        let running = Arc::new(Mutex::new((0, 0)));
+        let limit = Limit::new(3);
+
+        let tracked = Arc::clone(&running);
+        map((0..4).collect::<Vec<u32>>(), 4, move |_| {
+            let tracked = Arc::clone(&tracked);
+            let limit = limit.clone();
+            map((0..4).collect::<Vec<u32>>(), 4, move |_| {
+                limit.run(|| {
+                    {
+                        let mut running = tracked.lock().unwrap();
+                        running.0 += 1;
+                        running.1 = running.1.max(running.0);
+                    }
+                    thread::sleep(Duration::from_millis(5));
+                    tracked.lock().unwrap().0 -= 1;
+                })
+            });
+        });
+
+        assert!(running.lock().unwrap().1 <= 3);
+    }
+}
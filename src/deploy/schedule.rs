@@ -21,16 +21,17 @@ impl ScheduleTarget {
         let client = http::legacy_auth_client(user);
 
         log::info!("Pushing {} schedule(s)...", self.crons.len());
-        let res = client
-            .put(&schedule_worker_addr)
-            .header("Content-Type", "application/json")
-            .body(build_schedules_request(&self.crons))
-            .send()?;
+        let res = http::send(
+            client
+                .put(&schedule_worker_addr)
+                .header("Content-Type", "application/json")
+                .body(build_schedules_request(&self.crons)),
+        )?;
 
         let status = res.status();
         let text = res.text()?;
         if !status.is_success() {
-            anyhow::bail!(crate::format_api_errors(text))
+            anyhow::bail!(crate::ApiError::from_response(text))
         }
 
         Ok(self.crons.clone())
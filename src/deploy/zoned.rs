@@ -3,87 +3,224 @@ use std::fmt;
 use anyhow::Result;
 use serde::Serialize;
 
-use cloudflare::endpoints::workers::{CreateRoute, CreateRouteParams, ListRoutes};
+use cloudflare::endpoints::workers::{CreateRoute, CreateRouteParams, DeleteRoute, ListRoutes};
+use cloudflare::endpoints::zone::{ListZones, ListZonesParams};
 use cloudflare::framework::apiclient::ApiClient;
 
+use super::parallel;
 use crate::http;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::{Route, RouteConfig};
 use crate::terminal::message::{Message, StdOut};
+use crate::ApiError;
+
+/// The zone a set of routes is published to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Zone {
+    Id(String),
+    /// Resolved to an id through the zones API at deploy time
+    Name(String),
+}
+
+impl Zone {
+    pub fn id(&self, user: &GlobalUser) -> Result<String> {
+        match self {
+            Zone::Id(id) => Ok(id.clone()),
+            Zone::Name(name) => {
+                let client = http::cf_v4_client(user)?;
+                let zones = match client.request(&ListZones {
+                    params: ListZonesParams {
+                        name: Some(name.clone()),
+                        ..Default::default()
+                    },
+                }) {
+                    Ok(success) => success.result,
+                    Err(e) => anyhow::bail!(http::api_error(e, None)),
+                };
+
+                match zones.into_iter().find(|zone| &zone.name == name) {
+                    Some(zone) => Ok(zone.id),
+                    None => anyhow::bail!(
+                        "Could not find a zone named {} on your account; check `zone_name` in your configuration file",
+                        name
+                    ),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Zone::Id(id) => write!(f, "{}", id),
+            Zone::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct ZonedTarget {
-    pub zone_id: String,
+    pub zone: Zone,
     pub routes: Vec<Route>,
 }
 
 impl ZonedTarget {
-    pub fn build(script_name: &str, route_config: &RouteConfig) -> Result<Self> {
-        match route_config.zone_id.as_ref() {
-            Some(zone_id) if !zone_id.is_empty() => {
-                let new_route = |route: &String| Route {
-                    id: None,
-                    script: Some(script_name.to_string()),
-                    pattern: route.to_string(),
-                };
-                let routes: Vec<Route> = route_config
-                    .route
-                    .iter()
-                    .map(new_route)
-                    .chain(route_config.routes.iter().flatten().filter_map(|route| {
-                        if route.is_empty() {
-                            StdOut::warn("your configuration file contains an empty route");
-                            None
-                        } else {
-                            Some(new_route(route))
-                        }
-                    }))
-                    .collect();
-
-                Ok(Self {
-                    zone_id: zone_id.to_owned(),
-                    routes,
-                })
+    /// Groups the configured routes by zone, in the order each zone first
+    /// appears. Routes that don't name a zone go to the configuration's `zone_id`.
+    pub fn build(script_name: &str, route_config: &RouteConfig) -> Result<Vec<Self>> {
+        let default_zone = match route_config.zone_id.as_ref() {
+            Some(zone_id) if !zone_id.is_empty() => Some(Zone::Id(zone_id.to_owned())),
+            _ => None,
+        };
+
+        let mut targets: Vec<Self> = Vec::new();
+        let mut add_route = |pattern: &str, zone: Option<Zone>| -> Result<()> {
+            let zone = match zone.or_else(|| default_zone.clone()) {
+                Some(zone) => zone,
+                None => anyhow::bail!(
+                    "field `zone_id` is required to deploy to routes; set it, or give the route {} its own `zone_id` or `zone_name`",
+                    pattern
+                ),
+            };
+            let route = Route {
+                id: None,
+                script: Some(script_name.to_string()),
+                pattern: pattern.to_string(),
+            };
+
+            match targets.iter_mut().find(|target| target.zone == zone) {
+                Some(target) => target.routes.push(route),
+                None => targets.push(Self {
+                    zone,
+                    routes: vec![route],
+                }),
+            }
+            Ok(())
+        };
+
+        if let Some(route) = &route_config.route {
+            add_route(route, None)?;
+        }
+        for route in route_config.routes.iter().flatten() {
+            if route.pattern().is_empty() {
+                StdOut::warn("your configuration file contains an empty route");
+            } else {
+                add_route(route.pattern(), route.zone()?)?;
             }
-            _ => anyhow::bail!("field `zone_id` is required to deploy to routes"),
         }
+
+        Ok(targets)
     }
 
-    pub fn deploy(&self, user: &GlobalUser) -> Result<Vec<String>> {
-        log::info!("publishing to zone {}", self.zone_id);
+    /// Creates the target's routes, making requests as `limit` allows.
+    pub fn deploy(&self, user: &GlobalUser, limit: &parallel::Limit) -> Result<Vec<String>> {
+        log::info!("publishing to zone {}", self.zone);
 
-        let published_routes = publish_routes(user, self)?;
+        let zone_id = limit.run(|| self.zone.id(user))?;
+        let published_routes = publish_routes(user, &zone_id, &self.routes, limit)?;
+
+        // every route has been tried, so report all of the ones that failed
+        let mut failed = Vec::new();
+        let mut codes = Vec::new();
+        for result in &published_routes {
+            if let RouteUploadResult::Error((_, error)) = result {
+                failed.push(result.to_string());
+                codes.extend(&error.codes);
+            }
+        }
+        if !failed.is_empty() {
+            anyhow::bail!(ApiError {
+                codes,
+                message: failed.join("\n"),
+            })
+        }
 
         let display_results: Vec<String> = published_routes.iter().map(|r| r.to_string()).collect();
 
         Ok(display_results)
     }
+
+    /// Deletes the zone's routes that point to the script but aren't among
+    /// the target's routes, adding each route to `pruned` once it's deleted,
+    /// so that a failure partway still leaves a record of what was deleted.
+    pub fn prune(
+        &self,
+        user: &GlobalUser,
+        script_name: &str,
+        pruned: &mut Vec<Route>,
+    ) -> Result<()> {
+        log::info!("pruning routes in zone {}", self.zone);
+
+        let zone_id = self.zone.id(user)?;
+        let existing_routes = fetch_all(user, &zone_id)?;
+        let stale = stale_routes(script_name, &self.routes, &existing_routes);
+
+        let client = http::cf_v4_client(user)?;
+        for route in stale {
+            let identifier = match &route.id {
+                Some(id) => id,
+                None => {
+                    log::info!("Not deleting {}, which has no id", route.pattern);
+                    continue;
+                }
+            };
+            log::info!("Deleting your route {:#?}", &route.pattern);
+            if let Err(e) = client.request(&DeleteRoute {
+                zone_identifier: &zone_id,
+                identifier,
+            }) {
+                return Err(anyhow::Error::new(http::api_error(e, None))
+                    .context(format!("Could not delete the route {}", route.pattern)));
+            }
+            pruned.push(route);
+        }
+
+        Ok(())
+    }
+}
+
+/// The existing routes pointing to the script that aren't in `routes`.
+pub fn stale_routes(script_name: &str, routes: &[Route], existing_routes: &[Route]) -> Vec<Route> {
+    existing_routes
+        .iter()
+        .filter(|existing| existing.script.as_deref() == Some(script_name))
+        .filter(|existing| !routes.iter().any(|route| route.pattern == existing.pattern))
+        .cloned()
+        .collect()
 }
 
 pub fn publish_routes(
     user: &GlobalUser,
-    zoned_config: &ZonedTarget,
+    zone_id: &str,
+    routes: &[Route],
+    limit: &parallel::Limit,
 ) -> Result<Vec<RouteUploadResult>> {
     // For the moment, we'll just make this call once and make all our decisions based on the response.
     // There is a possibility of race conditions, but we just report back the results and allow the
     // user to decide how to proceed.
-    let existing_routes = fetch_all(user, &zoned_config.zone_id)?;
+    let existing_routes = limit.run(|| fetch_all(user, zone_id))?;
 
-    let deployed_routes = zoned_config
-        .routes
-        .iter()
-        .map(|route| deploy_route(user, &zoned_config.zone_id, route, &existing_routes))
-        .collect();
+    // routes are created concurrently, and reported in the order they're configured;
+    // `limit` is shared with the other targets, so it bounds requests overall
+    let user = user.clone();
+    let zone_id = zone_id.to_string();
+    let limit = limit.clone();
+    let deployed_routes = parallel::map(
+        routes.to_vec(),
+        parallel::MAX_CONCURRENT_REQUESTS,
+        move |route| limit.run(|| deploy_route(&user, &zone_id, &route, &existing_routes)),
+    );
 
     Ok(deployed_routes)
 }
 
-fn fetch_all(user: &GlobalUser, zone_identifier: &str) -> Result<Vec<Route>> {
+pub fn fetch_all(user: &GlobalUser, zone_identifier: &str) -> Result<Vec<Route>> {
     let client = http::cf_v4_client(user)?;
 
     let routes: Vec<Route> = match client.request(&ListRoutes { zone_identifier }) {
         Ok(success) => success.result.iter().map(Route::from).collect(),
-        Err(e) => anyhow::bail!("{}", http::format_error(e, None)), // TODO: add suggestion fn
+        Err(e) => anyhow::bail!(http::api_error(e, None)), // TODO: add suggestion fn
     };
 
     Ok(routes)
@@ -105,7 +242,7 @@ fn create(user: &GlobalUser, zone_identifier: &str, route: &Route) -> Result<Rou
             pattern: route.pattern.clone(),
             script: route.script.clone(),
         }),
-        Err(e) => anyhow::bail!("{}", http::format_error(e, Some(&routes_error_help))),
+        Err(e) => anyhow::bail!(http::api_error(e, Some(&routes_error_help))),
     }
 }
 
@@ -128,7 +265,7 @@ pub enum RouteUploadResult {
     Same(Route),
     Conflict(Route),
     New(Route),
-    Error((Route, String)),
+    Error((Route, ApiError)),
 }
 
 impl fmt::Display for RouteUploadResult {
@@ -142,8 +279,8 @@ impl fmt::Display for RouteUploadResult {
                 route.script.as_ref().unwrap_or(&"null worker".to_string())
             ),
             RouteUploadResult::New(route) => write!(f, "{} => created", route.pattern),
-            RouteUploadResult::Error((route, message)) => {
-                write!(f, "{} => creation failed: {}", route.pattern, message)
+            RouteUploadResult::Error((route, error)) => {
+                write!(f, "{} => creation failed: {}", route.pattern, error)
             }
         }
     }
@@ -186,7 +323,40 @@ fn deploy_route(
                 script: route.script.clone(),
                 pattern: route.pattern.clone(),
             },
-            e.to_string(),
+            ApiError {
+                codes: ApiError::codes_of(&e),
+                message: e.to_string(),
+            },
         )),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(pattern: &str, script: Option<&str>) -> Route {
+        Route {
+            id: Some(pattern.to_string()),
+            script: script.map(str::to_string),
+            pattern: pattern.to_string(),
+        }
+    }
+
+    #[test]
+    fn it_finds_routes_of_the_script_missing_from_the_configuration() {
+        let configured = vec![route("example.com/*", None)];
+        let existing = vec![
+            route("example.com/*", Some("worker")),
+            route("example.com/old/*", Some("worker")),
+            route("example.com/other/*", Some("other")),
+            route("example.com/none/*", None),
+        ];
+
+        assert_eq!(
+            stale_routes("worker", &configured, &existing),
+            vec![route("example.com/old/*", Some("worker"))]
+        );
+        assert!(stale_routes("worker", &existing, &existing).is_empty());
+    }
+}
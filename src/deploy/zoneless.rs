@@ -37,16 +37,17 @@ impl ZonelessTarget {
         let client = http::legacy_auth_client(user);
 
         log::info!("Making public on subdomain...");
-        let res = client
-            .post(&sd_worker_addr)
-            .header("Content-type", "application/json")
-            .body(build_subdomain_request())
-            .send()?;
+        let res = http::send(
+            client
+                .post(&sd_worker_addr)
+                .header("Content-type", "application/json")
+                .body(build_subdomain_request()),
+        )?;
 
         let status = res.status();
         let text = res.text()?;
         if !status.is_success() {
-            anyhow::bail!(crate::format_api_errors(text))
+            anyhow::bail!(crate::ApiError::from_response(text))
         }
 
         let deploy_address = format!("https://{}.{}.workers.dev", self.script_name, subdomain);
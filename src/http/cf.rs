@@ -9,12 +9,18 @@ use http::StatusCode;
 
 use anyhow::Result;
 
-use crate::http::{feature::headers, DEFAULT_HTTP_TIMEOUT_SECONDS};
+use crate::http::{feature::headers, RetryingApiClient, DEFAULT_HTTP_TIMEOUT_SECONDS};
 use crate::settings::global_user::GlobalUser;
 use crate::terminal::emoji;
 use crate::terminal::message::{Message, StdOut};
+use crate::ApiError;
 
-const CF_API_BASE_URL: &str = "CF_API_BASE_URL";
+pub(super) const CF_API_BASE_URL: &str = "CF_API_BASE_URL";
+
+/// Held by tests that set `CF_API_BASE_URL`, which every test shares.
+#[cfg(test)]
+pub(super) static CF_API_BASE_URL_LOCK: once_cell::sync::Lazy<std::sync::Mutex<()>> =
+    once_cell::sync::Lazy::new(|| std::sync::Mutex::new(()));
 
 // Allow endpoint to be configured via an environment variable
 pub fn get_environment() -> Result<Environment> {
@@ -32,7 +38,7 @@ pub fn get_environment() -> Result<Environment> {
     Ok(Environment::Custom(env_hostname))
 }
 
-pub fn cf_v4_client(user: &GlobalUser) -> Result<HttpApiClient> {
+pub fn cf_v4_client(user: &GlobalUser) -> Result<RetryingApiClient> {
     let config = HttpApiClientConfig {
         http_timeout: Duration::from_secs(DEFAULT_HTTP_TIMEOUT_SECONDS),
         default_headers: headers(None),
@@ -40,7 +46,8 @@ pub fn cf_v4_client(user: &GlobalUser) -> Result<HttpApiClient> {
 
     let environment = get_environment()?;
 
-    HttpApiClient::new(Credentials::from(user.to_owned()), config, environment)
+    let client = HttpApiClient::new(Credentials::from(user.to_owned()), config, environment)?;
+    Ok(RetryingApiClient::new(client))
 }
 
 pub fn cf_v4_api_client_async(user: &GlobalUser) -> Result<async_api::Client> {
@@ -79,6 +86,18 @@ pub fn format_error(e: ApiFailure, err_helper: Option<&dyn Fn(u16) -> &'static s
     }
 }
 
+/// Like `format_error`, but keeps the API error codes for machine-readable output.
+pub fn api_error(e: ApiFailure, err_helper: Option<&dyn Fn(u16) -> &'static str>) -> ApiError {
+    let codes = match &e {
+        ApiFailure::Error(_, api_errors) => api_errors.errors.iter().map(|e| e.code).collect(),
+        ApiFailure::Invalid(_) => Vec::new(),
+    };
+    ApiError {
+        codes,
+        message: format_error(e, err_helper),
+    }
+}
+
 // For handling cases where the API gateway returns errors via HTTP status codes
 // (no API-specific, more granular error code is given).
 fn print_status_code_context(status_code: StatusCode) {
@@ -99,6 +118,7 @@ mod tests {
     fn gets_environment_tests() {
         // Tests are run in parallel, so dividing these three tests can lead
         // to an undesired interleaving in set and unset of the environment variables.
+        let _lock = CF_API_BASE_URL_LOCK.lock().unwrap();
 
         // Test #1
         // Tests that the API endpoint base URL can be read from an environment variable
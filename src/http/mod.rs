@@ -1,8 +1,10 @@
 pub(self) mod cf;
 pub(crate) mod feature;
 pub(self) mod legacy;
+pub mod retry;
 
 pub const DEFAULT_HTTP_TIMEOUT_SECONDS: u64 = 60;
-pub use cf::{cf_v4_api_client_async, cf_v4_client, format_error, get_environment};
+pub use cf::{api_error, cf_v4_api_client_async, cf_v4_client, format_error, get_environment};
 pub use feature::Feature;
 pub use legacy::{client, featured_legacy_auth_client, legacy_auth_client};
+pub use retry::{send, send_rebuilt, RetryingApiClient};
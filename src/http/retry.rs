@@ -0,0 +1,536 @@
+use std::env;
+use std::error::Error;
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+use cloudflare::framework::apiclient::ApiClient;
+use cloudflare::framework::endpoint::{Endpoint, Method};
+use cloudflare::framework::response::{ApiFailure, ApiResponse, ApiResult};
+use cloudflare::framework::HttpApiClient;
+use once_cell::sync::OnceCell;
+use rand::Rng;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use serde::Serialize;
+
+/// The environment variable setting how many times a failed request is retried.
+pub const MAX_RETRIES_ENV: &str = "WRANGLER_MAX_RETRIES";
+
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(30);
+// Retrying before the API's rate limit window ends would only be rate limited
+// again, so `Retry-After` gets a limit of its own, only guarding against
+// nonsensical values.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(10 * 60);
+
+static CONFIGURED_MAX_RETRIES: OnceCell<u32> = OnceCell::new();
+
+/// Sets how many times failed requests are retried, overriding
+/// `WRANGLER_MAX_RETRIES`. Only the first call has any effect.
+pub fn configure(max_retries: u32) {
+    let _ = CONFIGURED_MAX_RETRIES.set(max_retries);
+}
+
+/// How failed requests are retried: with exponential backoff and full
+/// jitter, or after as long as a `Retry-After` header asks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    /// The longest backoff between retries
+    pub max_delay: Duration,
+    /// The longest `Retry-After` that is waited for
+    pub max_retry_after: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: DEFAULT_MAX_RETRIES,
+            base_delay: BASE_DELAY,
+            max_delay: MAX_DELAY,
+            max_retry_after: MAX_RETRY_AFTER,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The policy set by `--max-retries` or `WRANGLER_MAX_RETRIES`, if either is.
+    pub fn current() -> RetryPolicy {
+        let max_retries = match CONFIGURED_MAX_RETRIES.get() {
+            Some(max_retries) => *max_retries,
+            None => match env::var(MAX_RETRIES_ENV).map(|value| value.parse::<u32>()) {
+                Ok(Ok(max_retries)) => max_retries,
+                Ok(Err(_)) => {
+                    log::warn!("Ignoring {}, which isn't a number", MAX_RETRIES_ENV);
+                    DEFAULT_MAX_RETRIES
+                }
+                Err(_) => DEFAULT_MAX_RETRIES,
+            },
+        };
+
+        RetryPolicy {
+            max_retries,
+            ..Default::default()
+        }
+    }
+
+    /// How long to wait before retry number `attempt`, counting from zero.
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after.min(self.max_retry_after);
+        }
+
+        let backoff = self
+            .base_delay
+            .checked_mul(2u32.saturating_pow(attempt))
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+        let jitter = rand::thread_rng().gen_range(0..=backoff.as_millis() as u64);
+        Duration::from_millis(jitter)
+    }
+}
+
+/// Rate limited requests weren't processed, so retrying them is always safe.
+fn is_retryable_status(status: StatusCode, idempotent: bool) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || (idempotent && status.is_server_error())
+}
+
+fn is_transient(error: &reqwest::Error) -> bool {
+    if error.is_connect() || error.is_timeout() {
+        return true;
+    }
+
+    let mut source = error.source();
+    while let Some(cause) = source {
+        if let Some(io_error) = cause.downcast_ref::<io::Error>() {
+            return matches!(
+                io_error.kind(),
+                io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::UnexpectedEof
+            );
+        }
+        source = cause.source();
+    }
+    false
+}
+
+/// `Retry-After` in seconds; the HTTP date form isn't used by the API.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+fn is_idempotent(method: &reqwest::Method) -> bool {
+    [
+        reqwest::Method::GET,
+        reqwest::Method::HEAD,
+        reqwest::Method::OPTIONS,
+        reqwest::Method::PUT,
+        reqwest::Method::DELETE,
+    ]
+    .contains(method)
+}
+
+fn wait(policy: &RetryPolicy, attempt: u32, retry_after: Option<Duration>, reason: &str) {
+    let delay = policy.delay(attempt, retry_after);
+    log::info!(
+        "{}; retrying in {:?} ({}/{})",
+        reason,
+        delay,
+        attempt + 1,
+        policy.max_retries
+    );
+    thread::sleep(delay);
+}
+
+/// Sends a request made with one of the legacy clients, retrying it if it
+/// fails with a transient error. Requests with a streamed body, such as
+/// multipart forms with files, can't be sent twice and so aren't retried; send
+/// those with `send_rebuilt` instead.
+pub fn send(request: RequestBuilder) -> reqwest::Result<Response> {
+    send_with(&RetryPolicy::current(), request)
+}
+
+fn send_with(policy: &RetryPolicy, mut request: RequestBuilder) -> reqwest::Result<Response> {
+    let idempotent = request
+        .try_clone()
+        .and_then(|probe| probe.build().ok())
+        .map_or(false, |probe| is_idempotent(probe.method()));
+
+    let mut attempt = 0;
+    loop {
+        let retry = match request.try_clone() {
+            Some(retry) if attempt < policy.max_retries => retry,
+            _ => return request.send(),
+        };
+
+        let result = request.send();
+        match retry_reason(&result, idempotent) {
+            Some((retry_after, reason)) => wait(policy, attempt, retry_after, &reason),
+            None => return result,
+        }
+
+        attempt += 1;
+        request = retry;
+    }
+}
+
+/// Sends the request `build` makes with `client`, retrying it like `send`
+/// does. The request is built again for every attempt, so requests whose body
+/// can only be sent once, such as multipart forms, are retried too.
+pub fn send_rebuilt<F>(client: &Client, build: F) -> anyhow::Result<Response>
+where
+    F: FnMut() -> anyhow::Result<RequestBuilder>,
+{
+    send_rebuilt_with(&RetryPolicy::current(), client, build)
+}
+
+fn send_rebuilt_with<F>(
+    policy: &RetryPolicy,
+    client: &Client,
+    mut build: F,
+) -> anyhow::Result<Response>
+where
+    F: FnMut() -> anyhow::Result<RequestBuilder>,
+{
+    let mut attempt = 0;
+    loop {
+        let request = build()?.build()?;
+        let idempotent = is_idempotent(request.method());
+
+        let result = client.execute(request);
+        if attempt >= policy.max_retries {
+            return Ok(result?);
+        }
+        match retry_reason(&result, idempotent) {
+            Some((retry_after, reason)) => wait(policy, attempt, retry_after, &reason),
+            None => return Ok(result?),
+        }
+
+        attempt += 1;
+    }
+}
+
+// Why to retry a request, and how long the API asked to wait first, if it
+// should be retried at all
+fn retry_reason(
+    result: &reqwest::Result<Response>,
+    idempotent: bool,
+) -> Option<(Option<Duration>, String)> {
+    match result {
+        Ok(response) if is_retryable_status(response.status(), idempotent) => Some((
+            retry_after(response.headers()),
+            format!("The API responded with {}", response.status()),
+        )),
+        Err(e) if idempotent && is_transient(e) => {
+            Some((None, format!("The request failed: {}", e)))
+        }
+        _ => None,
+    }
+}
+
+/// A cloudflare-rs client that retries requests failing with a transient
+/// error, following the same policy as `send`. cloudflare-rs doesn't expose
+/// response headers, so `Retry-After` isn't honoured here.
+pub struct RetryingApiClient<C = HttpApiClient> {
+    client: C,
+    policy: RetryPolicy,
+}
+
+impl<C: ApiClient> RetryingApiClient<C> {
+    pub fn new(client: C) -> Self {
+        Self::with_policy(client, RetryPolicy::current())
+    }
+
+    pub fn with_policy(client: C, policy: RetryPolicy) -> Self {
+        RetryingApiClient { client, policy }
+    }
+}
+
+impl<C: ApiClient> ApiClient for RetryingApiClient<C> {
+    fn request<ResultType, QueryType, BodyType>(
+        &self,
+        endpoint: &dyn Endpoint<ResultType, QueryType, BodyType>,
+    ) -> ApiResponse<ResultType>
+    where
+        ResultType: ApiResult,
+        QueryType: Serialize,
+        BodyType: Serialize,
+    {
+        let idempotent = matches!(
+            endpoint.method(),
+            Method::Get | Method::Put | Method::Delete
+        );
+
+        let mut attempt = 0;
+        loop {
+            let result = self.client.request(endpoint);
+            if attempt >= self.policy.max_retries {
+                return result;
+            }
+
+            match &result {
+                Err(ApiFailure::Error(status, _)) if is_retryable_status(*status, idempotent) => {
+                    wait(
+                        &self.policy,
+                        attempt,
+                        None,
+                        &format!("The API responded with {}", status),
+                    )
+                }
+                Err(ApiFailure::Invalid(e)) if idempotent && is_transient(e) => wait(
+                    &self.policy,
+                    attempt,
+                    None,
+                    &format!("The request failed: {}", e),
+                ),
+                _ => return result,
+            }
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::{Arc, Mutex};
+
+    use cloudflare::endpoints::workers::ListRoutes;
+    use cloudflare::framework::auth::Credentials;
+    use cloudflare::framework::{Environment, HttpApiClientConfig};
+
+    use crate::http::{self, cf};
+    use crate::settings::global_user::GlobalUser;
+
+    fn policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+            max_retry_after: Duration::from_millis(50),
+        }
+    }
+
+    // Reads the whole request, so that the client isn't cut off while still
+    // sending its body
+    fn read_request(stream: &mut TcpStream) -> String {
+        let mut request = Vec::new();
+        let mut buffer = [0; 4096];
+        loop {
+            let read = stream.read(&mut buffer).unwrap();
+            request.extend_from_slice(&buffer[..read]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            let head_end = match text.find("\r\n\r\n") {
+                Some(end) => end + 4,
+                None if read > 0 => continue,
+                None => return text,
+            };
+            let head = text[..head_end].to_lowercase();
+            let complete = if head.contains("transfer-encoding: chunked") {
+                text.ends_with("0\r\n\r\n")
+            } else {
+                let length = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .and_then(|length| length.trim().parse().ok())
+                    .unwrap_or(0);
+                request.len() >= head_end + length
+            };
+            if complete || read == 0 {
+                return text;
+            }
+        }
+    }
+
+    /// A local API that gives each request the next of `responses`, as
+    /// `(status, headers, body)`, and records each request line.
+    fn mock_api(
+        responses: Vec<(u16, &'static str, &'static str)>,
+    ) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/client/v4/", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = Arc::clone(&requests);
+        thread::spawn(move || {
+            for (status, headers, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let request = read_request(&mut stream);
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push(request.lines().next().unwrap_or_default().to_string());
+
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+                    status,
+                    body.len(),
+                    headers,
+                    body
+                )
+                .unwrap();
+            }
+        });
+
+        (url, requests)
+    }
+
+    const ROUTES: &str = r#"{"success":true,"errors":[],"messages":[],"result":[]}"#;
+    const OVERLOADED: &str = r#"{"success":false,"errors":[{"code":10013,"message":"overloaded"}],"messages":[],"result":null}"#;
+
+    #[test]
+    fn it_retries_server_errors_of_idempotent_requests() {
+        let (url, requests) = mock_api(vec![
+            (503, "", OVERLOADED),
+            (502, "", OVERLOADED),
+            (200, "", ROUTES),
+        ]);
+
+        let response = send_with(&policy(3), reqwest::blocking::Client::new().get(&url)).unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(requests.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn it_does_not_retry_server_errors_of_other_requests() {
+        let (url, requests) = mock_api(vec![(503, "", OVERLOADED), (200, "", ROUTES)]);
+
+        let response = send_with(&policy(3), reqwest::blocking::Client::new().post(&url)).unwrap();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn it_retries_rate_limited_requests_after_retry_after() {
+        let (url, requests) = mock_api(vec![
+            (429, "Retry-After: 0\r\n", OVERLOADED),
+            (200, "", ROUTES),
+        ]);
+
+        let response = send_with(&policy(1), reqwest::blocking::Client::new().post(&url)).unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn it_gives_up_after_the_last_retry() {
+        let (url, requests) = mock_api(vec![
+            (500, "", OVERLOADED),
+            (500, "", OVERLOADED),
+            (200, "", ROUTES),
+        ]);
+
+        let response = send_with(&policy(1), reqwest::blocking::Client::new().get(&url)).unwrap();
+
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn it_retries_cloudflare_rs_requests() {
+        let (url, requests) = mock_api(vec![(503, "", OVERLOADED), (200, "", ROUTES)]);
+        let client = HttpApiClient::new(
+            Credentials::UserAuthToken {
+                token: "token".to_string(),
+            },
+            HttpApiClientConfig {
+                http_timeout: Duration::from_secs(5),
+                default_headers: HeaderMap::new(),
+            },
+            Environment::Custom(url::Url::parse(&url).unwrap()),
+        )
+        .unwrap();
+
+        let client = RetryingApiClient::with_policy(client, policy(3));
+        let routes = client
+            .request(&ListRoutes {
+                zone_identifier: "zone",
+            })
+            .unwrap();
+
+        assert!(routes.result.is_empty());
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].starts_with("GET /client/v4/zones/zone/workers/routes"));
+    }
+
+    #[test]
+    fn it_caps_the_delay() {
+        let policy = policy(10);
+
+        assert!(policy.delay(20, None) <= policy.max_delay);
+        assert_eq!(
+            policy.delay(0, Some(Duration::from_secs(3600))),
+            policy.max_retry_after
+        );
+    }
+
+    #[test]
+    fn it_waits_as_long_as_retry_after_asks() {
+        let policy = RetryPolicy::default();
+
+        assert_eq!(
+            policy.delay(0, Some(Duration::from_secs(60))),
+            Duration::from_secs(60)
+        );
+    }
+
+    #[test]
+    fn it_retries_multipart_requests_by_building_them_again() {
+        let (url, requests) = mock_api(vec![(503, "", OVERLOADED), (200, "", ROUTES)]);
+        let client = Client::new();
+
+        let response = send_rebuilt_with(&policy(3), &client, || {
+            let form = reqwest::blocking::multipart::Form::new().text("metadata", "{}");
+            Ok(client.put(&url).multipart(form))
+        })
+        .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn it_retries_requests_of_the_api_client() {
+        let (url, requests) = mock_api(vec![(503, "", OVERLOADED), (200, "", ROUTES)]);
+        let user = GlobalUser::ApiTokenAuth {
+            api_token: "token".to_string(),
+        };
+
+        let client = {
+            let _lock = cf::CF_API_BASE_URL_LOCK.lock().unwrap();
+            env::set_var(cf::CF_API_BASE_URL, &url);
+            let client = http::cf_v4_client(&user);
+            env::remove_var(cf::CF_API_BASE_URL);
+            client.unwrap()
+        };
+        let routes = client
+            .request(&ListRoutes {
+                zone_identifier: "zone",
+            })
+            .unwrap();
+
+        assert!(routes.result.is_empty());
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+}
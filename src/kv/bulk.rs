@@ -10,9 +10,10 @@ use cloudflare::framework::apiclient::ApiClient;
 use cloudflare::framework::auth::Credentials;
 use cloudflare::framework::{HttpApiClient, HttpApiClientConfig};
 
-use crate::commands::kv::format_error;
+use crate::commands::kv::api_error;
 use crate::http;
 use crate::http::feature::headers;
+use crate::http::RetryingApiClient;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 
@@ -24,7 +25,7 @@ const UPLOAD_MAX_SIZE: usize = 50 * 1024 * 1024;
 
 // Create a special API client that has a longer timeout than usual, given that KV operations
 // can be lengthy if payloads are large.
-fn bulk_api_client(user: &GlobalUser) -> Result<HttpApiClient> {
+fn bulk_api_client(user: &GlobalUser) -> Result<RetryingApiClient> {
     let config = HttpApiClientConfig {
         http_timeout: Duration::from_secs(5 * 60),
         default_headers: headers(None),
@@ -32,7 +33,8 @@ fn bulk_api_client(user: &GlobalUser) -> Result<HttpApiClient> {
 
     let environment = http::get_environment()?;
 
-    HttpApiClient::new(Credentials::from(user.to_owned()), config, environment)
+    let client = HttpApiClient::new(Credentials::from(user.to_owned()), config, environment)?;
+    Ok(RetryingApiClient::new(client))
 }
 
 pub fn put(
@@ -51,7 +53,7 @@ pub fn put(
             bulk_key_value_pairs: b.to_owned(),
         }) {
             Ok(_) => {}
-            Err(e) => anyhow::bail!("{}", format_error(e)),
+            Err(e) => anyhow::bail!(api_error(e)),
         }
 
         if let Some(pb) = &progress_bar {
@@ -78,7 +80,7 @@ pub fn delete(
             bulk_keys: b.to_owned(),
         }) {
             Ok(_) => {}
-            Err(e) => anyhow::bail!("{}", format_error(e)),
+            Err(e) => anyhow::bail!(api_error(e)),
         }
 
         if let Some(pb) = &progress_bar {
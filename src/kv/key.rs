@@ -6,14 +6,14 @@ use cloudflare::endpoints::workerskv::list_namespace_keys::ListNamespaceKeysPara
 use cloudflare::endpoints::workerskv::Key;
 use cloudflare::framework::apiclient::ApiClient;
 use cloudflare::framework::response::ApiFailure;
-use cloudflare::framework::HttpApiClient;
 
+use crate::http::RetryingApiClient;
 use crate::settings::toml::Target;
 
 pub struct KeyList {
     keys_result: Option<Vec<Key>>,
     prefix: Option<String>,
-    client: HttpApiClient,
+    client: RetryingApiClient,
     account_id: String,
     namespace_id: String,
     cursor: Option<String>,
@@ -23,7 +23,7 @@ pub struct KeyList {
 impl KeyList {
     pub fn new(
         target: &Target,
-        client: HttpApiClient,
+        client: RetryingApiClient,
         namespace_id: &str,
         prefix: Option<&str>,
     ) -> Result<KeyList> {
@@ -1,10 +1,11 @@
 use cloudflare::endpoints::workerskv::remove_namespace::RemoveNamespace;
 use cloudflare::framework::apiclient::ApiClient;
 use cloudflare::framework::response::{ApiFailure, ApiSuccess};
-use cloudflare::framework::HttpApiClient;
+
+use crate::http::RetryingApiClient;
 
 pub fn delete(
-    client: HttpApiClient,
+    client: RetryingApiClient,
     account_id: &str,
     id: &str,
 ) -> Result<ApiSuccess<()>, ApiFailure> {
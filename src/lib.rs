@@ -7,7 +7,10 @@ extern crate text_io;
 #[macro_use]
 extern crate erased_serde;
 
+use std::fmt;
+
 use cloudflare::framework::response::ApiErrors;
+use serde::Serialize;
 
 mod build;
 pub mod cli;
@@ -62,3 +65,62 @@ pub fn format_api_errors(raw: String) -> String {
     msg.push_str(&raw);
     msg
 }
+
+/// An error from the API that keeps the API's error codes, so that they can be
+/// reported in machine-readable output.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ApiError {
+    pub codes: Vec<u16>,
+    pub message: String,
+}
+
+impl ApiError {
+    /// An error response received by one of the legacy clients.
+    pub fn from_response(raw: String) -> ApiError {
+        ApiError {
+            codes: api_error_codes(&raw),
+            message: format_api_errors(raw),
+        }
+    }
+
+    /// The codes of every `ApiError` that caused `error`.
+    pub fn codes_of(error: &anyhow::Error) -> Vec<u16> {
+        error
+            .chain()
+            .filter_map(|cause| cause.downcast_ref::<ApiError>())
+            .flat_map(|api_error| api_error.codes.iter().copied())
+            .collect()
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+pub(crate) fn api_error_codes(raw: &str) -> Vec<u16> {
+    serde_json::from_str::<ApiErrors>(raw)
+        .map(|api_errors| api_errors.errors.iter().map(|e| e.code).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_the_codes_of_api_errors() {
+        let raw = r#"{"result":null,"success":false,"errors":[{"code":10021,"message":"script error"},{"code":10075,"message":"usage model"}],"messages":[]}"#;
+
+        let error = anyhow::Error::new(ApiError::from_response(raw.to_string()))
+            .context("Publishing failed");
+
+        assert_eq!(ApiError::codes_of(&error), vec![10021, 10075]);
+        assert!(ApiError::from_response("Bad Gateway".to_string())
+            .codes
+            .is_empty());
+    }
+}
@@ -5,8 +5,11 @@ extern crate tokio;
 
 use std::env;
 
+use wrangler::cli::publish::PublishMode;
 use wrangler::cli::{exec, Cli, Command};
+use wrangler::commands::PublishOptions;
 use wrangler::installer;
+use wrangler::settings::toml::ConfigFormat;
 use wrangler::terminal::message::{Message, StdErr};
 use wrangler::terminal::{emoji, styles};
 use wrangler::version::check_for_updates;
@@ -48,22 +51,35 @@ Docs migration guide: https://developers.cloudflare.com/workers/wrangler/migrati
 }
 
 fn run() -> Result<()> {
-    let cli = Cli::from_args();
+    let mut cli = Cli::from_args();
+    cli.check_workspace_flags()?;
+    cli.config = ConfigFormat::locate(&cli.config);
+    if let Some(max_retries) = cli.max_retries {
+        wrangler::http::retry::configure(max_retries);
+    }
     let cli_params = cli.clone();
 
     match cli.command {
-        Command::Config { api_key, no_verify } => exec::configure(api_key, no_verify),
+        Command::Config {
+            subcommand: Some(subcommand),
+            ..
+        } => exec::config_file(subcommand, &cli_params),
+        Command::Config {
+            api_key, no_verify, ..
+        } => exec::configure(api_key, no_verify),
         Command::Generate {
             name,
             site,
             template,
             target_type,
-        } => exec::generate(name, site, template, target_type),
+            format,
+        } => exec::generate(name, site, template, target_type, format),
         Command::Init {
             name,
             site,
             target_type,
-        } => exec::init(name, site, target_type),
+            format,
+        } => exec::init(name, site, target_type, format),
         Command::Build => exec::build(&cli_params),
         Command::Preview {
             method,
@@ -95,10 +111,30 @@ fn run() -> Result<()> {
             release,
             output,
             migration,
-        } => exec::publish(release, output, migration, &cli_params),
+            dry_run,
+            outdir,
+            diff,
+            prune_routes,
+            all_envs,
+            envs,
+        } => exec::publish(
+            release,
+            output,
+            migration,
+            PublishMode::new(dry_run, outdir, all_envs, envs),
+            PublishOptions {
+                diff,
+                prune_routes,
+                ..Default::default()
+            },
+            &cli_params,
+        ),
         Command::Subdomain { name } => exec::subdomain(name, &cli_params),
         Command::Route(route) => exec::route(route, &cli_params),
         Command::Secret(secret) => exec::secret(secret, &cli_params),
+        Command::Migrations(migrations) => exec::migrations(migrations, &cli_params),
+        Command::Deployments(deployments) => exec::deployments(deployments, &cli_params),
+        Command::Rollback { to } => exec::rollback(to, &cli_params),
         Command::R2(r2) => exec::r2_bucket(r2, &cli_params),
         Command::KvNamespace(namespace) => exec::kv_namespace(namespace, &cli_params),
         Command::KvKey(key) => exec::kv_key(key, &cli_params),
@@ -1,4 +1,5 @@
 use serde::Serialize;
+use serde_json::Value;
 
 #[derive(Serialize, Debug)]
 #[serde(tag = "type")]
@@ -30,6 +31,16 @@ pub enum Binding {
         name: String,
         text: String,
     },
+    Json {
+        name: String,
+        json: Value,
+    },
+    Service {
+        name: String,
+        service: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        environment: Option<String>,
+    },
 }
 
 impl Binding {
@@ -64,4 +75,16 @@ impl Binding {
     pub fn new_plain_text(name: String, text: String) -> Binding {
         Binding::PlainText { name, text }
     }
+
+    pub fn new_json(name: String, json: Value) -> Binding {
+        Binding::Json { name, json }
+    }
+
+    pub fn new_service(name: String, service: String, environment: Option<String>) -> Binding {
+        Binding::Service {
+            name,
+            service,
+            environment,
+        }
+    }
 }
@@ -0,0 +1,265 @@
+use std::ffi::OsStr;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Number, Value};
+use yaml_rust::yaml::{Hash, Yaml};
+use yaml_rust::{YamlEmitter, YamlLoader};
+
+/// The file formats wrangler can read its configuration from, detected by
+/// file extension.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl ConfigFormat {
+    // in the order they are looked for when wrangler.toml doesn't exist
+    const ALL: [ConfigFormat; 3] = [ConfigFormat::Toml, ConfigFormat::Json, ConfigFormat::Yaml];
+
+    pub fn from_path(path: &Path) -> ConfigFormat {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => ConfigFormat::Json,
+            Some("yaml") | Some("yml") => ConfigFormat::Yaml,
+            _ => ConfigFormat::Toml,
+        }
+    }
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            ConfigFormat::Toml => "wrangler.toml",
+            ConfigFormat::Json => "wrangler.json",
+            ConfigFormat::Yaml => "wrangler.yaml",
+        }
+    }
+
+    /// If `config_path` is a wrangler.toml that doesn't exist, look for a
+    /// wrangler.json or wrangler.yaml next to it instead.
+    pub fn locate(config_path: &Path) -> PathBuf {
+        if config_path.exists() || config_path.file_name() != Some(OsStr::new("wrangler.toml")) {
+            return config_path.to_path_buf();
+        }
+
+        let candidates = ConfigFormat::ALL
+            .iter()
+            .map(|format| format.file_name())
+            .chain(std::iter::once("wrangler.yml"));
+        for file_name in candidates {
+            let candidate = config_path.with_file_name(file_name);
+            if candidate.exists() {
+                log::info!("Using configuration file {}", candidate.display());
+                return candidate;
+            }
+        }
+
+        config_path.to_path_buf()
+    }
+
+    /// Find an existing configuration file of any format in `dir`.
+    pub fn existing(dir: &Path) -> Option<PathBuf> {
+        let located = ConfigFormat::locate(&dir.join(ConfigFormat::Toml.file_name()));
+        if located.exists() {
+            Some(located)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn parse(self, source: &str) -> Result<Value> {
+        match self {
+            ConfigFormat::Toml => Ok(serde_json::to_value(toml::from_str::<toml::Value>(
+                source,
+            )?)?),
+            ConfigFormat::Json => Ok(serde_json::from_str(source)?),
+            ConfigFormat::Yaml => {
+                let documents = YamlLoader::load_from_str(source)?;
+                match documents.into_iter().next() {
+                    Some(document) => yaml_to_json(document),
+                    None => Ok(Value::Object(Map::new())),
+                }
+            }
+        }
+    }
+
+    /// Convert the contents of a wrangler.toml into this format.
+    pub(crate) fn render(self, toml: &str) -> Result<String> {
+        let value = ConfigFormat::Toml.parse(toml)?;
+        match self {
+            ConfigFormat::Toml => Ok(toml.to_string()),
+            ConfigFormat::Json => Ok(format!("{}\n", serde_json::to_string_pretty(&value)?)),
+            ConfigFormat::Yaml => {
+                let mut yaml = String::new();
+                YamlEmitter::new(&mut yaml)
+                    .dump(&json_to_yaml(value))
+                    .map_err(|e| anyhow!("Could not write YAML: {:?}", e))?;
+                yaml.push('\n');
+                Ok(yaml)
+            }
+        }
+    }
+}
+
+impl Default for ConfigFormat {
+    fn default() -> Self {
+        ConfigFormat::Toml
+    }
+}
+
+impl fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let printable = match *self {
+            ConfigFormat::Toml => "toml",
+            ConfigFormat::Json => "json",
+            ConfigFormat::Yaml => "yaml",
+        };
+        write!(f, "{}", printable)
+    }
+}
+
+impl FromStr for ConfigFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "toml" => Ok(ConfigFormat::Toml),
+            "json" => Ok(ConfigFormat::Json),
+            "yaml" | "yml" => Ok(ConfigFormat::Yaml),
+            _ => Err(anyhow!(
+                "{} is not a valid configuration format; must be one of toml, json or yaml",
+                s
+            )),
+        }
+    }
+}
+
+fn yaml_to_json(yaml: Yaml) -> Result<Value> {
+    Ok(match yaml {
+        Yaml::Null => Value::Null,
+        Yaml::Boolean(b) => Value::Bool(b),
+        Yaml::Integer(i) => Value::Number(i.into()),
+        Yaml::Real(r) => r
+            .parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(|| anyhow!("{} is not a valid number", r))?,
+        Yaml::String(s) => Value::String(s),
+        Yaml::Array(values) => Value::Array(
+            values
+                .into_iter()
+                .map(yaml_to_json)
+                .collect::<Result<_>>()?,
+        ),
+        Yaml::Hash(hash) => {
+            let mut map = Map::new();
+            for (key, value) in hash {
+                let key = match key {
+                    Yaml::String(key) => key,
+                    Yaml::Integer(key) => key.to_string(),
+                    key => anyhow::bail!("{:?} is not a valid key", key),
+                };
+                map.insert(key, yaml_to_json(value)?);
+            }
+            Value::Object(map)
+        }
+        Yaml::Alias(_) | Yaml::BadValue => anyhow::bail!("unsupported YAML value"),
+    })
+}
+
+fn json_to_yaml(value: Value) -> Yaml {
+    match value {
+        Value::Null => Yaml::Null,
+        Value::Bool(b) => Yaml::Boolean(b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Yaml::Integer(i),
+            None => Yaml::Real(n.to_string()),
+        },
+        Value::String(s) => Yaml::String(s),
+        Value::Array(values) => Yaml::Array(values.into_iter().map(json_to_yaml).collect()),
+        Value::Object(map) => {
+            let mut hash = Hash::new();
+            for (key, value) in map {
+                hash.insert(Yaml::String(key), json_to_yaml(value));
+            }
+            Yaml::Hash(hash)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML: &str = r#"
+name = "worker"
+type = "javascript"
+workers_dev = true
+compatibility_date = "2022-01-01"
+
+[vars]
+DEBUG = "false"
+
+[[kv_namespaces]]
+binding = "CACHE"
+id = "somecrazylongidentifierstring"
+"#;
+
+    #[test]
+    fn it_detects_formats_by_extension() {
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("wrangler.toml")),
+            ConfigFormat::Toml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config/wrangler.json")),
+            ConfigFormat::Json
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("wrangler.yml")),
+            ConfigFormat::Yaml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("wrangler")),
+            ConfigFormat::Toml
+        );
+    }
+
+    #[test]
+    fn it_renders_every_format_to_the_same_config() {
+        let expected = ConfigFormat::Toml.parse(TOML).unwrap();
+
+        for format in &ConfigFormat::ALL {
+            let rendered = format.render(TOML).unwrap();
+            assert_eq!(format.parse(&rendered).unwrap(), expected, "{}", format);
+        }
+    }
+
+    #[test]
+    fn it_prefers_wrangler_toml_when_locating() {
+        let dir = tempfile::tempdir().unwrap();
+        let toml_path = dir.path().join("wrangler.toml");
+
+        assert_eq!(ConfigFormat::locate(&toml_path), toml_path);
+        assert_eq!(ConfigFormat::existing(dir.path()), None);
+
+        std::fs::write(dir.path().join("wrangler.yaml"), "name: worker").unwrap();
+        assert_eq!(
+            ConfigFormat::locate(&toml_path),
+            dir.path().join("wrangler.yaml")
+        );
+
+        std::fs::write(dir.path().join("wrangler.json"), "{}").unwrap();
+        assert_eq!(
+            ConfigFormat::locate(&toml_path),
+            dir.path().join("wrangler.json")
+        );
+
+        std::fs::write(&toml_path, "").unwrap();
+        assert_eq!(ConfigFormat::locate(&toml_path), toml_path);
+        assert_eq!(ConfigFormat::existing(dir.path()), Some(toml_path));
+    }
+}
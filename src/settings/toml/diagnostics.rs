@@ -0,0 +1,1191 @@
+use std::env;
+use std::fmt;
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::Serialize;
+use serde_json::Value;
+use toml_edit::{Document, Item, TableLike};
+
+use super::extends;
+use super::interpolate;
+use super::{Manifest, Workspace};
+use crate::terminal::styles;
+
+// Compatibility flags understood by the Workers runtime. Anything else is most
+// likely a typo, which the API would otherwise reject at publish time.
+const KNOWN_COMPATIBILITY_FLAGS: &[&str] = &[
+    "capture_async_api_throws",
+    "do_not_capture_async_api_throws",
+    "durable_object_fetch_allows_relative_url",
+    "durable_object_fetch_requires_full_url",
+    "export_commonjs_default",
+    "export_commonjs_namespace",
+    "fetch_refuses_unknown_protocols",
+    "fetch_treats_unknown_protocols_as_http",
+    "formdata_parser_converts_files_to_strings",
+    "formdata_parser_supports_files",
+    "global_navigator",
+    "html_rewriter_treats_esi_include_as_void_tag",
+    "minimal_subrequests",
+    "no_global_navigator",
+    "no_minimal_subrequests",
+    "r2_list_honor_include",
+    "streams_enable_constructors",
+    "streams_disable_constructors",
+    "transformstream_enable_standard_constructor",
+    "transformstream_disable_standard_constructor",
+    "url_original",
+    "url_standard",
+];
+
+const CRON_FIELDS: [(&str, u32, u32); 5] = [
+    ("minute", 0, 59),
+    ("hour", 0, 23),
+    ("day of month", 1, 31),
+    ("month", 1, 12),
+    ("day of week", 0, 7),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "{}", styles::warning("error")),
+            Severity::Warning => write!(f, "{}", styles::highlight("warning")),
+        }
+    }
+}
+
+/// A single problem found in a configuration file. `line` and `column` are 1-based.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.line, self.column, self.severity, self.message
+        )
+    }
+}
+
+/// Run every semantic check against the top level of a configuration file and
+/// each of its `[env.*]` tables, returning the diagnostics sorted by position.
+pub fn check(source: &str) -> Vec<Diagnostic> {
+    check_with(source, None)
+}
+
+/// Like [`check`], for the configuration file at `config_path` as commands read
+/// it: keys it leaves to the files it extends, or to the root of its workspace,
+/// aren't reported as missing.
+pub fn check_file(config_path: &Path, source: &str) -> Vec<Diagnostic> {
+    match resolve(config_path) {
+        Ok(resolved) => check_with(source, Some(&resolved)),
+        Err(e) => {
+            let mut checker = Checker::new(source, None);
+            // syntax errors are reported with a position by the checks themselves
+            if source.parse::<Document>().is_ok() {
+                checker.report_at(Severity::Error, (0, 0), e.to_string());
+            }
+            checker.diagnostics.extend(check(source));
+            checker.finish()
+        }
+    }
+}
+
+// The configuration file merged over everything it inherits from
+fn resolve(config_path: &Path) -> Result<Value> {
+    let mut value = extends::load(config_path)?.value;
+    if let Some(workspace) = Workspace::containing(config_path)? {
+        value = workspace.inherit(value, config_path)?;
+    }
+    if interpolate::enabled() {
+        interpolate::interpolate(&mut value)?;
+    }
+    Ok(value)
+}
+
+fn check_with(source: &str, resolved: Option<&Value>) -> Vec<Diagnostic> {
+    let mut checker = Checker::new(source, resolved);
+
+    let document = match source.parse::<Document>() {
+        Ok(document) => document,
+        Err(e) => {
+            // toml_edit doesn't expose the position of a parse error, toml-rs does
+            let (position, message) = match toml::from_str::<toml::Value>(source) {
+                Err(e) => (e.line_col(), trim_position(e.to_string())),
+                Ok(_) => (None, e.to_string()),
+            };
+            checker.report_at(Severity::Error, position.unwrap_or((0, 0)), message);
+            return checker.finish();
+        }
+    };
+
+    let own = toml::from_str::<Manifest>(source);
+    match resolved {
+        None => {
+            if let Err(e) = own {
+                let position = e.line_col().unwrap_or((0, 0));
+                checker.report_at(Severity::Error, position, trim_position(e.to_string()));
+            }
+        }
+        // only what's still wrong once inherited keys are filled in is reported,
+        // where this file is to blame if it is
+        Some(resolved) => {
+            if let Err(e) = serde_json::from_value::<Manifest>(resolved.clone()) {
+                // toml-rs names the key after the same message
+                match own {
+                    Err(own) if own.to_string().starts_with(&e.to_string()) => {
+                        let position = own.line_col().unwrap_or((0, 0));
+                        checker.report_at(
+                            Severity::Error,
+                            position,
+                            trim_position(own.to_string()),
+                        );
+                    }
+                    _ => checker.report_at(Severity::Error, (0, 0), e.to_string()),
+                }
+            }
+        }
+    }
+
+    let top_level = document.as_table();
+    checker.check_scope(top_level, top_level, &KeyPath::default());
+
+    let mut names = Vec::new();
+    if let Some(name) = get_str(top_level, "name") {
+        names.push(name);
+    }
+    if let Some(environments) = get(top_level, "env").and_then(Item::as_table_like) {
+        for (env_name, environment) in environments.iter() {
+            let environment = match environment.as_table_like() {
+                Some(environment) => environment,
+                None => continue,
+            };
+            let path = KeyPath::default().key("env").key(env_name);
+            checker.check_scope(environment, top_level, &path);
+
+            if let Some(name) = get_str(environment, "name") {
+                if names.contains(&name) {
+                    checker.report(
+                        Severity::Error,
+                        &path.key("name"),
+                        None,
+                        format!(
+                            "the name \"{}\" is already used by another environment; each name in your configuration file must be unique",
+                            name
+                        ),
+                    );
+                }
+                names.push(name);
+            }
+        }
+    }
+
+    checker.finish()
+}
+
+// toml-rs appends " at line X column Y" to its messages; we report that separately.
+fn trim_position(message: String) -> String {
+    match message.rfind(" at line ") {
+        Some(index) => message[..index].to_string(),
+        None => message,
+    }
+}
+
+struct Checker<'a> {
+    source_map: SourceMap<'a>,
+    // the configuration as commands read it, when it inherits keys from elsewhere
+    resolved: Option<&'a Value>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Checker<'a> {
+    fn new(source: &'a str, resolved: Option<&'a Value>) -> Self {
+        Checker {
+            source_map: SourceMap::new(source),
+            resolved,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    // Whether `key` of the table at `path` is set once inherited keys are filled in
+    fn is_resolved(&self, path: &KeyPath, key: &str) -> bool {
+        let mut value = match self.resolved {
+            Some(resolved) => resolved,
+            None => return false,
+        };
+        for segment in &path.0 {
+            let next = match segment {
+                Segment::Key(key) => value.get(key.as_str()),
+                Segment::Index(index) => value.get(*index),
+            };
+            value = match next {
+                Some(next) => next,
+                None => return false,
+            };
+        }
+        value.get(key).map_or(false, |value| match value {
+            Value::Null => false,
+            Value::String(value) => !value.is_empty(),
+            _ => true,
+        })
+    }
+
+    fn finish(mut self) -> Vec<Diagnostic> {
+        self.diagnostics
+            .sort_by(|a, b| (a.line, a.column).cmp(&(b.line, b.column)));
+        self.diagnostics
+    }
+
+    fn report(
+        &mut self,
+        severity: Severity,
+        path: &KeyPath,
+        needle: Option<&str>,
+        message: String,
+    ) {
+        let position = self.source_map.locate(path, needle);
+        self.report_at(severity, position, message);
+    }
+
+    fn report_at(&mut self, severity: Severity, (line, column): (usize, usize), message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            line: line + 1,
+            column: column + 1,
+            message,
+        });
+    }
+
+    fn check_scope(&mut self, scope: &dyn TableLike, top_level: &dyn TableLike, path: &KeyPath) {
+        self.check_kv_namespaces(scope, path);
+        self.check_r2_buckets(scope, path);
+        self.check_services(scope, path);
+        self.check_durable_objects(scope, top_level, path);
+        self.check_routes(scope, top_level, path);
+        self.check_triggers(scope, path);
+        self.check_compatibility(scope, path);
+    }
+
+    fn check_kv_namespaces(&mut self, scope: &dyn TableLike, path: &KeyPath) {
+        for key in &["kv_namespaces", "kv-namespaces"] {
+            let namespaces = match get(scope, key) {
+                Some(namespaces) => entries(namespaces),
+                None => continue,
+            };
+            for (index, namespace) in namespaces.into_iter().enumerate() {
+                let entry = path.key(key).index(index);
+                let binding = get_str(namespace, "binding");
+                if binding.is_none() {
+                    self.report(
+                        Severity::Error,
+                        &entry,
+                        None,
+                        "KV namespace is missing `binding`".to_string(),
+                    );
+                }
+                if get_str(namespace, "id").map_or(true, str::is_empty) {
+                    self.report(
+                        Severity::Error,
+                        &entry,
+                        None,
+                        format!(
+                            "KV namespace \"{}\" is missing `id`",
+                            binding.unwrap_or_default()
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_r2_buckets(&mut self, scope: &dyn TableLike, path: &KeyPath) {
+        let buckets = match get(scope, "r2_buckets") {
+            Some(buckets) => entries(buckets),
+            None => return,
+        };
+        for (index, bucket) in buckets.into_iter().enumerate() {
+            let entry = path.key("r2_buckets").index(index);
+            let binding = get_str(bucket, "binding");
+            if binding.is_none() {
+                self.report(
+                    Severity::Error,
+                    &entry,
+                    None,
+                    "R2 bucket is missing `binding`".to_string(),
+                );
+            }
+            if get_str(bucket, "bucket_name").map_or(true, str::is_empty) {
+                self.report(
+                    Severity::Error,
+                    &entry,
+                    None,
+                    format!(
+                        "R2 bucket \"{}\" is missing `bucket_name`",
+                        binding.unwrap_or_default()
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_services(&mut self, scope: &dyn TableLike, path: &KeyPath) {
+        let services = match get(scope, "services") {
+            Some(services) => entries(services),
+            None => return,
+        };
+        for (index, service) in services.into_iter().enumerate() {
+            let entry = path.key("services").index(index);
+            let binding = get_str(service, "binding");
+            if binding.is_none() {
+                self.report(
+                    Severity::Error,
+                    &entry,
+                    None,
+                    "Service binding is missing `binding`".to_string(),
+                );
+            }
+            if get_str(service, "service").map_or(true, str::is_empty) {
+                self.report(
+                    Severity::Error,
+                    &entry,
+                    None,
+                    format!(
+                        "Service binding \"{}\" is missing `service`",
+                        binding.unwrap_or_default()
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_durable_objects(
+        &mut self,
+        scope: &dyn TableLike,
+        top_level: &dyn TableLike,
+        path: &KeyPath,
+    ) {
+        let durable_objects = match get(scope, "durable_objects").and_then(Item::as_table_like) {
+            Some(durable_objects) => durable_objects,
+            None => return,
+        };
+        let has_migrations = get(scope, "migrations").is_some()
+            || get(top_level, "migrations").is_some()
+            || self.is_resolved(path, "migrations")
+            || self.is_resolved(&KeyPath::default(), "migrations");
+
+        for key in &["classes", "bindings"] {
+            let classes = match get(durable_objects, key) {
+                Some(classes) => entries(classes),
+                None => continue,
+            };
+            for (index, class) in classes.into_iter().enumerate() {
+                let entry = path.key("durable_objects").key(key).index(index);
+                let binding = get_str(class, "binding").or_else(|| get_str(class, "name"));
+                let class_name = get_str(class, "class_name");
+                if binding.is_none() {
+                    self.report(
+                        Severity::Error,
+                        &entry,
+                        None,
+                        "Durable Object binding is missing `binding`".to_string(),
+                    );
+                }
+                match class_name {
+                    None => self.report(
+                        Severity::Error,
+                        &entry,
+                        None,
+                        format!(
+                            "Durable Object binding \"{}\" is missing `class_name`",
+                            binding.unwrap_or_default()
+                        ),
+                    ),
+                    Some(class_name) if !has_migrations && get(class, "script_name").is_none() => {
+                        self.report(
+                            Severity::Warning,
+                            &entry,
+                            None,
+                            format!(
+                                "Durable Object class \"{}\" is implemented by this script, but no [[migrations]] are defined; it won't be created unless you publish with `--new-class {}`",
+                                class_name, class_name
+                            ),
+                        )
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+    }
+
+    fn check_routes(&mut self, scope: &dyn TableLike, top_level: &dyn TableLike, path: &KeyPath) {
+        let mut has_routes = false;
+
+        if let Some(route) = get_str(scope, "route") {
+            if route.is_empty() {
+                self.report(
+                    Severity::Warning,
+                    &path.key("route"),
+                    None,
+                    "`route` is empty and will be ignored".to_string(),
+                );
+            } else {
+                has_routes = true;
+                self.check_route_pattern(route, &path.key("route"));
+            }
+        }
+
+        if let Some(routes) = get(scope, "routes").and_then(Item::as_array) {
+            if routes.is_empty() {
+                self.report(
+                    Severity::Warning,
+                    &path.key("routes"),
+                    None,
+                    "`routes` is empty".to_string(),
+                );
+            }
+            for (index, route) in routes.iter().enumerate() {
+                if let Some(route) = route.as_str() {
+                    if route.is_empty() {
+                        self.report(
+                            Severity::Warning,
+                            &path.key("routes"),
+                            Some("\"\""),
+                            "your configuration file contains an empty route".to_string(),
+                        );
+                    } else {
+                        has_routes = true;
+                        self.check_route_pattern(route, &path.key("routes"));
+                    }
+                } else if let Some(route) = route.as_inline_table() {
+                    // routes without a zone of their own use the configuration's `zone_id`
+                    if !self.check_zoned_route(route, &path.key("routes").index(index)) {
+                        has_routes = true;
+                    }
+                }
+            }
+        }
+
+        let has_zone_id = [scope, top_level]
+            .iter()
+            .any(|table| get_str(*table, "zone_id").map_or(false, |id| !id.is_empty()))
+            || self.is_resolved(path, "zone_id")
+            || self.is_resolved(&KeyPath::default(), "zone_id")
+            || env::var("CF_ZONE_ID").is_ok();
+        if has_routes && !has_zone_id {
+            let key = if get(scope, "routes").is_some() {
+                "routes"
+            } else {
+                "route"
+            };
+            self.report(
+                Severity::Error,
+                &path.key(key),
+                None,
+                "routes are specified with no zone, specify `zone_id` in your configuration file"
+                    .to_string(),
+            );
+        }
+    }
+
+    // Checks a `{ pattern = "...", zone_id = "..." }` route, returning whether
+    // it names its own zone.
+    fn check_zoned_route(&mut self, route: &dyn TableLike, path: &KeyPath) -> bool {
+        let pattern = match get_str(route, "pattern") {
+            Some(pattern) => pattern,
+            None => {
+                self.report(
+                    Severity::Error,
+                    path,
+                    None,
+                    "a route table needs a `pattern`".to_string(),
+                );
+                return true;
+            }
+        };
+        self.check_route_pattern(pattern, path);
+
+        let has_zone_id = get(route, "zone_id").is_some();
+        let has_zone_name = get(route, "zone_name").is_some();
+        if has_zone_id && has_zone_name {
+            let needle = format!("\"{}\"", pattern);
+            self.report(
+                Severity::Error,
+                path,
+                Some(needle.as_str()),
+                format!(
+                    "the route \"{}\" sets both `zone_id` and `zone_name`; set only one",
+                    pattern
+                ),
+            );
+        }
+        has_zone_id || has_zone_name
+    }
+
+    fn check_route_pattern(&mut self, pattern: &str, path: &KeyPath) {
+        if is_interpolated(pattern) {
+            return;
+        }
+        if let Some(problem) = route_pattern_error(pattern) {
+            let needle = format!("\"{}\"", pattern);
+            self.report(
+                Severity::Error,
+                path,
+                Some(needle.as_str()),
+                format!("invalid route \"{}\": {}", pattern, problem),
+            );
+        }
+    }
+
+    fn check_triggers(&mut self, scope: &dyn TableLike, path: &KeyPath) {
+        let crons = match get(scope, "triggers")
+            .and_then(Item::as_table_like)
+            .and_then(|triggers| get(triggers, "crons"))
+            .and_then(Item::as_array)
+        {
+            Some(crons) => crons,
+            None => return,
+        };
+        for cron in crons.iter().filter_map(|cron| cron.as_str()) {
+            if let Some(problem) = cron_error(cron) {
+                let needle = format!("\"{}\"", cron);
+                self.report(
+                    Severity::Error,
+                    &path.key("triggers").key("crons"),
+                    Some(needle.as_str()),
+                    format!("invalid cron \"{}\": {}", cron, problem),
+                );
+            }
+        }
+    }
+
+    fn check_compatibility(&mut self, scope: &dyn TableLike, path: &KeyPath) {
+        if let Some(date) = get_str(scope, "compatibility_date") {
+            if !is_interpolated(date) && NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() {
+                self.report(
+                    Severity::Error,
+                    &path.key("compatibility_date"),
+                    None,
+                    format!(
+                        "compatibility_date \"{}\" must be a date in the form YYYY-MM-DD",
+                        date
+                    ),
+                );
+            }
+        }
+
+        if let Some(flags) = get(scope, "compatibility_flags").and_then(Item::as_array) {
+            for flag in flags.iter().filter_map(|flag| flag.as_str()) {
+                if !KNOWN_COMPATIBILITY_FLAGS.contains(&flag) {
+                    let needle = format!("\"{}\"", flag);
+                    self.report(
+                        Severity::Warning,
+                        &path.key("compatibility_flags"),
+                        Some(needle.as_str()),
+                        format!("unknown compatibility flag \"{}\"", flag),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn get<'a>(table: &'a dyn TableLike, key: &str) -> Option<&'a Item> {
+    table.get(key).filter(|item| !item.is_none())
+}
+
+fn get_str<'a>(table: &'a dyn TableLike, key: &str) -> Option<&'a str> {
+    get(table, key).and_then(Item::as_str)
+}
+
+// Both `[[kv_namespaces]]` and `kv_namespaces = [{ ... }]` are valid ways to
+// write a list of tables.
+fn entries(item: &Item) -> Vec<&dyn TableLike> {
+    if let Some(tables) = item.as_array_of_tables() {
+        tables.iter().map(|table| table as &dyn TableLike).collect()
+    } else if let Some(array) = item.as_array() {
+        array
+            .iter()
+            .filter_map(|value| value.as_inline_table())
+            .map(|table| table as &dyn TableLike)
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+// values containing `${VAR}` are only known once the configuration is read
+fn is_interpolated(value: &str) -> bool {
+    interpolate::enabled() && value.contains("${")
+}
+
+fn route_pattern_error(pattern: &str) -> Option<&'static str> {
+    if pattern.chars().any(char::is_whitespace) {
+        return Some("route patterns cannot contain whitespace");
+    }
+    if pattern.contains('?') || pattern.contains('#') {
+        return Some("route patterns cannot contain query strings or fragments");
+    }
+    let without_scheme = match pattern.find("://") {
+        Some(index) => match &pattern[..index] {
+            "http" | "https" => &pattern[index + 3..],
+            _ => return Some("only http and https schemes are supported"),
+        },
+        None => pattern,
+    };
+    let (host, path) = match without_scheme.find('/') {
+        Some(index) => without_scheme.split_at(index),
+        None => (without_scheme, ""),
+    };
+    if host.is_empty() || host == "*" {
+        return Some("route patterns must include a hostname");
+    }
+    if host.chars().skip(1).any(|c| c == '*') {
+        return Some("a wildcard is only allowed at the start of the hostname");
+    }
+    if let Some(index) = path.find('*') {
+        if index != path.len() - 1 {
+            return Some("a wildcard is only allowed at the end of the path");
+        }
+    }
+    None
+}
+
+fn cron_error(cron: &str) -> Option<String> {
+    let fields: Vec<&str> = cron.split_whitespace().collect();
+    if fields.len() != CRON_FIELDS.len() {
+        return Some(format!(
+            "expected 5 fields (minute, hour, day of month, month, day of week), found {}",
+            fields.len()
+        ));
+    }
+
+    for (field, (name, min, max)) in fields.iter().zip(CRON_FIELDS.iter()) {
+        let valid_chars = field
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "*,-/?#".contains(c));
+        if !valid_chars {
+            return Some(format!("the {} field \"{}\" is invalid", name, field));
+        }
+
+        for part in field.split(',') {
+            let (base, step) = match part.find('/') {
+                Some(index) => (&part[..index], Some(&part[index + 1..])),
+                None => (part, None),
+            };
+            if let Some(step) = step {
+                if step.parse::<u32>().map_or(true, |step| step == 0) {
+                    return Some(format!(
+                        "the {} field \"{}\" has an invalid step",
+                        name, field
+                    ));
+                }
+            }
+            let out_of_range = base
+                .split('-')
+                .filter_map(|value| value.parse::<u32>().ok())
+                .any(|value| value < *min || value > *max);
+            if base.is_empty() || out_of_range {
+                return Some(format!(
+                    "the {} field \"{}\" must be between {} and {}",
+                    name, field, min, max
+                ));
+            }
+        }
+    }
+
+    None
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// The path to a value in the configuration file, e.g. `env.staging.kv_namespaces[1]`.
+#[derive(Clone, Debug, Default)]
+struct KeyPath(Vec<Segment>);
+
+impl KeyPath {
+    fn key(&self, key: &str) -> KeyPath {
+        let mut path = self.clone();
+        path.0.push(Segment::Key(key.to_string()));
+        path
+    }
+
+    fn index(&self, index: usize) -> KeyPath {
+        let mut path = self.clone();
+        path.0.push(Segment::Index(index));
+        path
+    }
+}
+
+struct Header {
+    line: usize,
+    column: usize,
+    name: Vec<String>,
+    is_array: bool,
+}
+
+struct Section {
+    position: (usize, usize),
+    start: usize,
+    end: usize,
+}
+
+/// toml_edit preserves formatting but doesn't track positions, so we map
+/// key paths back onto the source text ourselves. Positions are 0-based.
+struct SourceMap<'a> {
+    lines: Vec<&'a str>,
+    headers: Vec<Header>,
+}
+
+impl<'a> SourceMap<'a> {
+    fn new(source: &'a str) -> Self {
+        let lines: Vec<&str> = source.lines().collect();
+        let headers = lines
+            .iter()
+            .enumerate()
+            .filter_map(|(line, text)| {
+                let trimmed = text.trim_start();
+                let (inner, is_array) = match trimmed.strip_prefix("[[") {
+                    Some(inner) => (inner, true),
+                    None => (trimmed.strip_prefix('[')?, false),
+                };
+                let end = inner.find(']')?;
+                Some(Header {
+                    line,
+                    column: text.len() - trimmed.len(),
+                    name: split_key(&inner[..end]),
+                    is_array,
+                })
+            })
+            .collect();
+
+        SourceMap { lines, headers }
+    }
+
+    /// Find the most specific position for `path`, falling back to its closest
+    /// ancestor when the path itself isn't written out. If `needle` is provided,
+    /// its first occurrence after that position is preferred.
+    fn locate(&self, path: &KeyPath, needle: Option<&str>) -> (usize, usize) {
+        let segments = &path.0;
+        for split in (0..=segments.len()).rev() {
+            let (head, rest) = segments.split_at(split);
+            let section = match self.section(head) {
+                Some(section) => section,
+                None => continue,
+            };
+
+            if rest.is_empty() {
+                if head.is_empty() {
+                    continue;
+                }
+                return self.refine(section.position, section.end, needle);
+            }
+
+            let keys: Vec<&str> = rest
+                .iter()
+                .take_while(|segment| matches!(segment, Segment::Key(_)))
+                .filter_map(|segment| match segment {
+                    Segment::Key(key) => Some(key.as_str()),
+                    Segment::Index(_) => None,
+                })
+                .collect();
+            for count in (1..=keys.len()).rev() {
+                if let Some(mut position) = self.find_key(&section, &keys[..count]) {
+                    if let Some(Segment::Index(index)) = rest.get(count) {
+                        if let Some(entry) = self.find_inline_table(position, section.end, *index) {
+                            position = entry;
+                        }
+                    }
+                    return self.refine(position, section.end, needle);
+                }
+            }
+        }
+
+        (0, 0)
+    }
+
+    fn section(&self, head: &[Segment]) -> Option<Section> {
+        let first_header = self.headers.first().map_or(self.lines.len(), |h| h.line);
+        if head.is_empty() {
+            return Some(Section {
+                position: (0, 0),
+                start: 0,
+                end: first_header,
+            });
+        }
+
+        let (name, nth) = match head.split_last() {
+            Some((Segment::Index(index), name)) => (name, Some(*index)),
+            _ => (head, None),
+        };
+        let name = name
+            .iter()
+            .map(|segment| match segment {
+                Segment::Key(key) => Some(key.as_str()),
+                Segment::Index(_) => None,
+            })
+            .collect::<Option<Vec<&str>>>()?;
+
+        let mut matching = self
+            .headers
+            .iter()
+            .enumerate()
+            .filter(|(_, header)| header.is_array == nth.is_some() && header.name == name);
+        let (index, header) = matching.nth(nth.unwrap_or(0))?;
+
+        Some(Section {
+            position: (header.line, header.column),
+            start: header.line + 1,
+            end: self
+                .headers
+                .get(index + 1)
+                .map_or(self.lines.len(), |next| next.line),
+        })
+    }
+
+    fn find_key(&self, section: &Section, keys: &[&str]) -> Option<(usize, usize)> {
+        (section.start..section.end).find_map(|line| {
+            let text = self.lines[line];
+            let trimmed = text.trim_start();
+            if trimmed.starts_with('#') || trimmed.starts_with('[') {
+                return None;
+            }
+            let key = &trimmed[..trimmed.find('=')?];
+            if split_key(key) == keys {
+                Some((line, text.len() - trimmed.len()))
+            } else {
+                None
+            }
+        })
+    }
+
+    // Find the `index`th inline table in the array that starts at `from`.
+    fn find_inline_table(
+        &self,
+        from: (usize, usize),
+        end: usize,
+        index: usize,
+    ) -> Option<(usize, usize)> {
+        let mut seen = 0;
+        let mut in_string = false;
+        let mut escaped = false;
+        for (line, text) in self.lines.iter().enumerate().take(end).skip(from.0) {
+            let offset = if line == from.0 { from.1 } else { 0 };
+            for (column, c) in text[offset..].char_indices() {
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' if in_string => escaped = true,
+                    '"' => in_string = !in_string,
+                    '#' if !in_string => break,
+                    '{' if !in_string => {
+                        if seen == index {
+                            return Some((line, offset + column));
+                        }
+                        seen += 1;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        None
+    }
+
+    fn refine(&self, from: (usize, usize), end: usize, needle: Option<&str>) -> (usize, usize) {
+        let needle = match needle {
+            Some(needle) => needle,
+            None => return self.to_char_position(from),
+        };
+        for (line, text) in self.lines.iter().enumerate().take(end).skip(from.0) {
+            let offset = if line == from.0 { from.1 } else { 0 };
+            if let Some(column) = text[offset..].find(needle) {
+                return self.to_char_position((line, offset + column));
+            }
+        }
+        self.to_char_position(from)
+    }
+
+    // Columns are tracked as byte offsets internally but reported in characters.
+    fn to_char_position(&self, (line, column): (usize, usize)) -> (usize, usize) {
+        match self.lines.get(line) {
+            Some(text) => (line, text[..column].chars().count()),
+            None => (line, column),
+        }
+    }
+}
+
+// Split a (possibly dotted, possibly quoted) TOML key into its segments.
+fn split_key(key: &str) -> Vec<String> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    for c in key.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '.') => segments.push(std::mem::take(&mut current)),
+            (None, c) if c.is_whitespace() => {}
+            (None, c) => current.push(c),
+        }
+    }
+    segments.push(current);
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(source: &str) -> Vec<(Severity, usize, usize)> {
+        check(source)
+            .into_iter()
+            .map(|d| (d.severity, d.line, d.column))
+            .collect()
+    }
+
+    #[test]
+    fn it_accepts_a_valid_config() {
+        let source = r#"
+name = "worker"
+type = "javascript"
+account_id = "1234"
+zone_id = "5678"
+routes = ["example.com/*", "*.example.com/api/*"]
+compatibility_date = "2022-01-01"
+compatibility_flags = ["formdata_parser_supports_files"]
+
+[triggers]
+crons = ["*/15 * * * *", "0 0 1 JAN MON"]
+
+[env.staging]
+route = "staging.example.com/*"
+kv_namespaces = [{ binding = "KV", id = "abc" }]
+"#;
+        assert_eq!(check(source), vec![]);
+    }
+
+    #[test]
+    fn it_reports_missing_kv_ids_in_environments() {
+        let source = r#"name = "worker"
+type = "javascript"
+
+[env.production]
+name = "worker-production"
+
+[[env.production.kv_namespaces]]
+binding = "FIRST"
+id = "1234"
+
+[[env.production.kv_namespaces]]
+binding = "SECOND"
+"#;
+        assert_eq!(positions(source), vec![(Severity::Error, 11, 1)]);
+        assert!(check(source)[0].message.contains("\"SECOND\""));
+    }
+
+    #[test]
+    fn it_points_at_inline_table_entries() {
+        let source = r#"name = "worker"
+type = "javascript"
+r2_buckets = [
+  { binding = "A", bucket_name = "a" },
+  { binding = "B" },
+]
+"#;
+        assert_eq!(positions(source), vec![(Severity::Error, 5, 3)]);
+    }
+
+    #[test]
+    fn it_reports_invalid_and_empty_routes() {
+        let source = r#"name = "worker"
+type = "javascript"
+zone_id = "1234"
+routes = ["example.com/*", "", "example.com/*/foo"]
+"#;
+        assert_eq!(
+            positions(source),
+            vec![(Severity::Warning, 4, 28), (Severity::Error, 4, 32)]
+        );
+    }
+
+    #[test]
+    fn it_requires_a_zone_for_routes() {
+        let source = r#"name = "worker"
+type = "javascript"
+route = "example.com/*"
+"#;
+        let diagnostics = check(source);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 3);
+        assert!(diagnostics[0].message.contains("zone_id"));
+    }
+
+    #[test]
+    fn it_checks_routes_with_their_own_zone() {
+        let source = r#"name = "worker"
+type = "javascript"
+routes = [
+  { pattern = "example.com/*", zone_name = "example.com" },
+  { pattern = "example.org/?q", zone_id = "1234" },
+  { pattern = "example.net/*", zone_id = "1234", zone_name = "example.net" },
+]
+"#;
+        assert_eq!(
+            positions(source),
+            vec![(Severity::Error, 5, 15), (Severity::Error, 6, 15)]
+        );
+
+        let without_zone = r#"name = "worker"
+type = "javascript"
+routes = [{ pattern = "example.com/*" }]
+"#;
+        let diagnostics = check(without_zone);
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("zone_id"));
+    }
+
+    #[test]
+    fn it_checks_extending_files_with_what_they_inherit() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("base.toml"),
+            "type = \"javascript\"\nzone_id = \"1234\"\n",
+        )
+        .unwrap();
+        let source = r#"extends = "base.toml"
+name = "worker"
+routes = ["example.com/*"]
+workers_dev = "yes"
+"#;
+        let config_path = dir.path().join("wrangler.toml");
+        std::fs::write(&config_path, source).unwrap();
+
+        let diagnostics = check_file(&config_path, source);
+        assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+        assert_eq!(diagnostics[0].line, 4);
+        assert!(diagnostics[0].message.contains("boolean"));
+
+        // the same file on its own is missing `type` and `zone_id`
+        assert!(check(source).len() > 1);
+    }
+
+    #[test]
+    fn it_checks_workspace_members_with_what_they_inherit() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("wrangler.toml"),
+            r#"name = "monorepo"
+type = "javascript"
+account_id = "1234"
+zone_id = "5678"
+
+[workspace]
+members = ["workers/api"]
+"#,
+        )
+        .unwrap();
+        let source = "name = \"api\"\nroutes = [\"example.com/api/*\"]\n";
+        let config_path = dir.path().join("workers/api/wrangler.toml");
+        std::fs::create_dir_all(config_path.parent().unwrap()).unwrap();
+        std::fs::write(&config_path, source).unwrap();
+
+        assert_eq!(check_file(&config_path, source), vec![]);
+    }
+
+    #[test]
+    fn it_warns_about_durable_objects_without_migrations() {
+        let source = r#"name = "worker"
+type = "javascript"
+
+[durable_objects]
+classes = [
+  { binding = "REMOTE", class_name = "Remote", script_name = "other" },
+  { binding = "COUNTER", class_name = "Counter" },
+]
+"#;
+        assert_eq!(positions(source), vec![(Severity::Warning, 7, 3)]);
+
+        let with_migrations = format!(
+            "{}\n[[migrations]]\ntag = \"v1\"\nnew_classes = [\"Counter\"]\n",
+            source
+        );
+        assert_eq!(check(&with_migrations), vec![]);
+    }
+
+    #[test]
+    fn it_reports_bad_crons_and_unknown_flags() {
+        let source = r#"name = "worker"
+type = "javascript"
+compatibility_flags = ["formdata_parser_supports_filez"]
+
+[env.staging.triggers]
+crons = ["0 * * *", "61 * * * *"]
+"#;
+        assert_eq!(
+            positions(source),
+            vec![
+                (Severity::Warning, 3, 24),
+                (Severity::Error, 6, 10),
+                (Severity::Error, 6, 21),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reports_duplicate_names() {
+        let source = r#"name = "worker"
+type = "javascript"
+
+[env.staging]
+name = "worker"
+"#;
+        assert_eq!(positions(source), vec![(Severity::Error, 5, 1)]);
+    }
+
+    #[test]
+    fn it_reports_syntax_errors_with_a_position() {
+        let source = "name = \"worker\"\ntype = \n";
+        let diagnostics = check(source);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].line, 2);
+    }
+
+    #[test]
+    fn it_validates_route_patterns() {
+        assert_eq!(route_pattern_error("example.com/*"), None);
+        assert_eq!(route_pattern_error("https://*.example.com/api*"), None);
+        assert!(route_pattern_error("example.com/?q=1").is_some());
+        assert!(route_pattern_error("ftp://example.com/*").is_some());
+        assert!(route_pattern_error("/*").is_some());
+        assert!(route_pattern_error("exa*mple.com/*").is_some());
+    }
+
+    #[test]
+    fn it_validates_crons() {
+        assert_eq!(cron_error("*/30 1-5 * * 1,3,5"), None);
+        assert!(cron_error("* * * *").is_some());
+        assert!(cron_error("* 24 * * *").is_some());
+        assert!(cron_error("*/0 * * * *").is_some());
+        assert!(cron_error("* * * * $").is_some());
+    }
+}
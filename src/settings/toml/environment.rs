@@ -2,15 +2,19 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use serde_with::rust::string_empty_as_none;
 
 use crate::settings::toml::builder::Builder;
 use crate::settings::toml::durable_objects::DurableObjects;
 use crate::settings::toml::kv_namespace::ConfigKvNamespace;
+use crate::settings::toml::migrations::MigrationConfig;
 use crate::settings::toml::r2_bucket::ConfigR2Bucket;
-use crate::settings::toml::route::RouteConfig;
+use crate::settings::toml::route::{ConfigRoute, RouteConfig};
+use crate::settings::toml::service::ServiceBinding;
 use crate::settings::toml::site::Site;
 use crate::settings::toml::triggers::Triggers;
+use crate::settings::toml::UsageModel;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Environment {
@@ -20,9 +24,10 @@ pub struct Environment {
     pub workers_dev: Option<bool>,
     #[serde(default, with = "string_empty_as_none")]
     pub route: Option<String>,
-    pub routes: Option<Vec<String>>,
+    pub routes: Option<Vec<ConfigRoute>>,
     #[serde(default, with = "string_empty_as_none")]
     pub zone_id: Option<String>,
+    pub custom_domains: Option<Vec<String>>,
     pub webpack_config: Option<String>,
     pub build: Option<Builder>,
     pub private: Option<bool>,
@@ -30,13 +35,40 @@ pub struct Environment {
     #[serde(alias = "kv-namespaces")]
     pub kv_namespaces: Option<Vec<ConfigKvNamespace>>,
     pub r2_buckets: Option<Vec<ConfigR2Bucket>>,
-    pub vars: Option<HashMap<String, String>>,
+    pub services: Option<Vec<ServiceBinding>>,
+    pub vars: Option<HashMap<String, Value>>,
+    pub secrets: Option<Vec<String>>,
     pub text_blobs: Option<HashMap<String, PathBuf>>,
     pub triggers: Option<Triggers>,
     pub durable_objects: Option<DurableObjects>,
+    pub migrations: Option<Vec<MigrationConfig>>,
+    #[serde(default, with = "string_empty_as_none")]
+    pub usage_model: Option<UsageModel>,
+    pub compatibility_date: Option<String>,
+    pub compatibility_flags: Option<Vec<String>>,
+    pub wasm_modules: Option<HashMap<String, PathBuf>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inherit: Vec<InheritedKey>,
+}
+
+/// Keys that are not inherited from the top level by default, but which an
+/// environment can opt in to inheriting with `inherit = [...]`.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InheritedKey {
+    Vars,
+    DurableObjects,
+    #[serde(alias = "kv-namespaces")]
+    KvNamespaces,
+    R2Buckets,
+    Services,
 }
 
 impl Environment {
+    pub fn inherits(&self, key: InheritedKey) -> bool {
+        self.inherit.contains(&key)
+    }
+
     pub fn route_config(
         &self,
         top_level_account_id: Option<String>,
@@ -0,0 +1,444 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::iter;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Value};
+
+use super::config_format::ConfigFormat;
+use super::manifest::Manifest;
+
+/// The key naming the configuration file(s) a configuration file starts from.
+pub const KEY: &str = "extends";
+
+/// Keys of a top level or environment holding a path.
+const PATH_KEYS: [&[&str]; 6] = [
+    &["build", "cwd"],
+    &["build", "watch_dir"],
+    &["build", "upload", "dir"],
+    &["site", "bucket"],
+    &["site", "entry-point"],
+    &["webpack_config"],
+];
+
+/// Tables of a top level or environment whose values are all paths.
+const PATH_TABLES: [&str; 2] = ["text_blobs", "wasm_modules"];
+
+/// A configuration file deep-merged over the files it extends, remembering
+/// which file set each value.
+#[derive(Debug)]
+pub(super) struct Extended {
+    pub value: Value,
+    // dotted paths of the values set by each file
+    origins: BTreeMap<String, PathBuf>,
+}
+
+/// Whether a configuration file sets a top-level `extends` key. Files that
+/// don't parse are left for the usual loader to report.
+pub(super) fn is_extending(config_path: &Path, source: &str) -> bool {
+    // most files never mention the key, so don't parse those twice
+    if !source.contains(KEY) {
+        return false;
+    }
+    match ConfigFormat::from_path(config_path).parse(source) {
+        Ok(Value::Object(table)) => table.contains_key(KEY),
+        _ => false,
+    }
+}
+
+/// Reads a configuration file, merging it over the files named by its
+/// `extends` key. Tables are merged key by key, any other value (including
+/// arrays) replaces the one it extends. `extends` paths are relative to the
+/// file that names them, and may be a list, in which case later files win.
+/// Relative paths set by the files extended are made relative to the
+/// directory of `config_path`, like the paths it sets itself.
+pub(super) fn load(config_path: &Path) -> Result<Extended> {
+    let mut extended = Extended {
+        value: Value::Object(Map::new()),
+        origins: BTreeMap::new(),
+    };
+    load_into(
+        config_path,
+        None,
+        Path::new(""),
+        &mut Vec::new(),
+        &mut extended,
+    )?;
+    Ok(extended)
+}
+
+// `location` is the directory of `config_path`, relative to the directory of
+// the file `load` started from
+fn load_into(
+    config_path: &Path,
+    extended_by: Option<&Path>,
+    location: &Path,
+    chain: &mut Vec<PathBuf>,
+    extended: &mut Extended,
+) -> Result<()> {
+    let describe = || match extended_by {
+        Some(child) => format!(
+            "{} (extended by {})",
+            config_path.display(),
+            child.display()
+        ),
+        None => config_path.display().to_string(),
+    };
+
+    let canonical = fs::canonicalize(config_path)
+        .map_err(|e| anyhow!("Could not read {}: {}", describe(), e))?;
+    if let Some(start) = chain.iter().position(|path| path == &canonical) {
+        let cycle: Vec<String> = chain[start..]
+            .iter()
+            .chain(iter::once(&canonical))
+            .map(|path| path.display().to_string())
+            .collect();
+        anyhow::bail!(
+            "Configuration files extend each other in a cycle: {}",
+            cycle.join(" -> ")
+        )
+    }
+
+    let source = fs::read_to_string(config_path)?;
+    let mut value = ConfigFormat::from_path(config_path)
+        .parse(&source)
+        .map_err(|e| anyhow!("Could not parse {}: {}", describe(), e))?;
+    let bases = match &mut value {
+        Value::Object(table) => table.remove(KEY),
+        _ => anyhow::bail!("{} is not a table", describe()),
+    };
+
+    if let Some(bases) = bases {
+        let bases = match bases {
+            Value::String(base) => vec![base],
+            Value::Array(bases) if bases.iter().all(Value::is_string) => bases
+                .into_iter()
+                .filter_map(|base| base.as_str().map(str::to_string))
+                .collect(),
+            _ => anyhow::bail!(
+                "`{}` in {} must be a path, or a list of paths, to other configuration files",
+                KEY,
+                config_path.display()
+            ),
+        };
+
+        let dir = config_path.parent().unwrap_or_else(|| Path::new(""));
+        chain.push(canonical);
+        for base in bases {
+            let base = Path::new(&base);
+            let base_location = location.join(base.parent().unwrap_or_else(|| Path::new("")));
+            load_into(
+                &dir.join(base),
+                Some(config_path),
+                &base_location,
+                chain,
+                extended,
+            )?;
+        }
+        chain.pop();
+    }
+
+    if !location.as_os_str().is_empty() {
+        rebase_scope(&mut value, location);
+        if let Some(Value::Object(environments)) = value.get_mut("env") {
+            for environment in environments.values_mut() {
+                rebase_scope(environment, location);
+            }
+        }
+    }
+
+    merge(
+        &mut extended.value,
+        value,
+        String::new(),
+        config_path,
+        &mut extended.origins,
+    );
+    Ok(())
+}
+
+/// Merges `value` over `base` the way extended files are merged: tables key by
+/// key, and any other value replacing the one it's merged over.
+pub(super) fn deep_merge(base: &mut Value, value: Value) {
+    merge(
+        base,
+        value,
+        String::new(),
+        Path::new(""),
+        &mut BTreeMap::new(),
+    );
+}
+
+// Makes the relative paths of a top level or environment relative to the
+// directory of the file `load` started from
+fn rebase_scope(scope: &mut Value, location: &Path) {
+    for keys in &PATH_KEYS {
+        let mut value = Some(&mut *scope);
+        for key in keys.iter() {
+            value = value.and_then(|value| value.get_mut(*key));
+        }
+        if let Some(value) = value {
+            rebase(value, location);
+        }
+    }
+    for key in &PATH_TABLES {
+        if let Some(Value::Object(paths)) = scope.get_mut(*key) {
+            for path in paths.values_mut() {
+                rebase(path, location);
+            }
+        }
+    }
+}
+
+fn rebase(value: &mut Value, location: &Path) {
+    if let Value::String(path) = value {
+        // paths starting from a variable are only known once it's interpolated
+        if !path.contains("${") {
+            *path = location.join(&*path).to_string_lossy().to_string();
+        }
+    }
+}
+
+fn merge(
+    base: &mut Value,
+    value: Value,
+    path: String,
+    file: &Path,
+    origins: &mut BTreeMap<String, PathBuf>,
+) {
+    match (base, value) {
+        (Value::Object(base), Value::Object(table)) => {
+            for (key, value) in table {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                merge(
+                    base.entry(key).or_insert(Value::Null),
+                    value,
+                    path,
+                    file,
+                    origins,
+                );
+            }
+        }
+        (base, value) => {
+            // whatever the replaced value was made of came from other files
+            origins.retain(|origin, _| !is_within(origin, &path));
+            origins.insert(path, file.to_path_buf());
+            *base = value;
+        }
+    }
+}
+
+fn is_within(path: &str, parent: &str) -> bool {
+    path == parent
+        || (path.starts_with(parent) && path[parent.len()..].starts_with('.'))
+        || parent.is_empty()
+}
+
+impl Extended {
+    /// Adds the files that set the offending top level key to an error from
+    /// deserializing the merged configuration.
+    pub fn explain(&self, error: anyhow::Error) -> anyhow::Error {
+        let table = match &self.value {
+            Value::Object(table) => table,
+            _ => return error,
+        };
+
+        // deserialize each key on its own to find the one that's wrong
+        let culprit = table.iter().find(|(key, value)| {
+            let mut trial = Map::new();
+            if key.as_str() != "type" {
+                trial.insert("type".to_string(), Value::from("javascript"));
+            }
+            trial.insert(key.to_string(), (*value).clone());
+            serde_json::from_value::<Manifest>(Value::Object(trial)).is_err()
+        });
+
+        match culprit {
+            Some((key, _)) => {
+                let mut files: Vec<String> = Vec::new();
+                for (path, file) in &self.origins {
+                    let file = file.display().to_string();
+                    if is_within(path, key) && !files.contains(&file) {
+                        files.push(file);
+                    }
+                }
+                anyhow!("{}\n`{}` is set in {}", error, key, files.join(", "))
+            }
+            None => error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, name: &str, contents: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn it_deep_merges_over_the_base() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "base.toml",
+            r#"
+account_id = "sharedaccountid"
+compatibility_flags = ["formdata_parser_supports_files"]
+
+[build.upload]
+format = "modules"
+main = "./worker.mjs"
+"#,
+        );
+        let config_path = write(
+            dir.path(),
+            "worker/wrangler.toml",
+            r#"
+extends = "../base.toml"
+name = "worker"
+type = "javascript"
+compatibility_flags = []
+
+[build.upload]
+main = "./index.mjs"
+"#,
+        );
+
+        let extended = load(&config_path).unwrap();
+        let value = &extended.value;
+
+        assert!(value.get(KEY).is_none());
+        assert_eq!(value["account_id"], "sharedaccountid");
+        assert_eq!(value["compatibility_flags"], serde_json::json!([]));
+        assert_eq!(value["build"]["upload"]["format"], "modules");
+        assert_eq!(value["build"]["upload"]["main"], "./index.mjs");
+        assert_eq!(
+            extended.origins["build.upload.format"],
+            config_path.parent().unwrap().join("../base.toml")
+        );
+    }
+
+    #[test]
+    fn it_only_extends_from_the_top_level_key() {
+        let path = Path::new("wrangler.toml");
+
+        assert!(is_extending(path, "extends = \"base.toml\"\n"));
+        assert!(!is_extending(path, "name = \"extends-worker\"\n"));
+        assert!(!is_extending(
+            path,
+            "name = \"worker\"\n\n[vars]\nextends = \"base.toml\"\n"
+        ));
+    }
+
+    #[test]
+    fn it_rebases_paths_of_extended_files() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "shared/base.toml",
+            r#"
+type = "javascript"
+
+[site]
+bucket = "public"
+
+[build]
+cwd = "/absolute"
+[build.upload]
+format = "modules"
+main = "./index.mjs"
+dir = "dist"
+
+[text_blobs]
+TEMPLATE = "templates/index.html"
+
+[env.staging.site]
+bucket = "staging"
+"#,
+        );
+        let config_path = write(
+            dir.path(),
+            "worker/wrangler.toml",
+            "extends = \"../shared/base.toml\"\nname = \"worker\"\nwebpack_config = \"webpack.js\"\n",
+        );
+
+        let value = load(&config_path).unwrap().value;
+
+        let shared = Path::new("../shared");
+        assert_eq!(
+            value["site"]["bucket"],
+            shared.join("public").to_str().unwrap()
+        );
+        assert_eq!(
+            value["build"]["upload"]["dir"],
+            shared.join("dist").to_str().unwrap()
+        );
+        assert_eq!(
+            value["text_blobs"]["TEMPLATE"],
+            shared.join("templates/index.html").to_str().unwrap()
+        );
+        assert_eq!(
+            value["env"]["staging"]["site"]["bucket"],
+            shared.join("staging").to_str().unwrap()
+        );
+        // absolute paths, module names and paths set by the file itself are left alone
+        assert_eq!(value["build"]["cwd"], "/absolute");
+        assert_eq!(value["build"]["upload"]["main"], "./index.mjs");
+        assert_eq!(value["webpack_config"], "webpack.js");
+    }
+
+    #[test]
+    fn it_detects_cycles() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "a.toml", "extends = \"b.toml\"\n");
+        write(dir.path(), "b.toml", "extends = [\"c.toml\", \"a.toml\"]\n");
+        write(dir.path(), "c.toml", "name = \"worker\"\n");
+
+        let error = load(&dir.path().join("a.toml")).unwrap_err().to_string();
+
+        assert!(error.contains("cycle"), "{}", error);
+        assert!(error.contains("b.toml -> "), "{}", error);
+    }
+
+    #[test]
+    fn it_names_missing_bases() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = write(dir.path(), "wrangler.toml", "extends = \"base.toml\"\n");
+
+        let error = load(&config_path).unwrap_err().to_string();
+
+        assert!(error.contains("base.toml (extended by"), "{}", error);
+    }
+
+    #[test]
+    fn it_explains_where_bad_values_came_from() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = write(dir.path(), "base.toml", "workers_dev = \"yes\"\n");
+        let config_path = write(
+            dir.path(),
+            "wrangler.toml",
+            "extends = \"base.toml\"\nname = \"worker\"\ntype = \"javascript\"\n",
+        );
+
+        let extended = load(&config_path).unwrap();
+        let error = extended
+            .explain(anyhow!("invalid type: string \"yes\", expected a boolean"))
+            .to_string();
+
+        assert!(
+            error.ends_with(&format!("`workers_dev` is set in {}", base.display())),
+            "{}",
+            error
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::BTreeSet;
+use std::env;
+
+use anyhow::Result;
+use serde_json::Value;
+
+/// Setting this environment variable to any non-empty value turns off
+/// interpolation, for configuration files that contain a literal `${`.
+pub const DISABLE_INTERPOLATION: &str = "WRANGLER_NO_INTERPOLATION";
+
+pub fn enabled() -> bool {
+    env::var_os(DISABLE_INTERPOLATION).map_or(true, |value| value.is_empty())
+}
+
+/// Replace `${VAR}` and `${VAR:-default}` in every string value of a parsed
+/// configuration file with the value of the environment variable `VAR`.
+/// `$${` is an escaped `${`.
+pub fn interpolate(value: &mut Value) -> Result<()> {
+    interpolate_with(value, &|name| env::var(name).ok())
+}
+
+fn interpolate_with(value: &mut Value, lookup: &dyn Fn(&str) -> Option<String>) -> Result<()> {
+    let mut undefined = BTreeSet::new();
+    interpolate_value(value, lookup, &mut undefined)?;
+
+    if !undefined.is_empty() {
+        anyhow::bail!(
+            "Your configuration file references environment variables that are not set: {}\nSet them, give them a default with `${{VAR:-default}}`, or set {}=1 to turn off interpolation.",
+            undefined.into_iter().collect::<Vec<_>>().join(", "),
+            DISABLE_INTERPOLATION
+        )
+    }
+
+    Ok(())
+}
+
+fn interpolate_value(
+    value: &mut Value,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    undefined: &mut BTreeSet<String>,
+) -> Result<()> {
+    match value {
+        Value::String(s) => {
+            if s.contains('$') {
+                *s = interpolate_str(s, lookup, undefined)?;
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                interpolate_value(value, lookup, undefined)?;
+            }
+        }
+        Value::Object(object) => {
+            for (_, value) in object.iter_mut() {
+                interpolate_value(value, lookup, undefined)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn interpolate_str(
+    s: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    undefined: &mut BTreeSet<String>,
+) -> Result<String> {
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(after) = rest.strip_prefix("$${") {
+            result.push_str("${");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let end = match after.find('}') {
+                Some(end) => end,
+                None => anyhow::bail!("Unterminated `${{` in configuration value \"{}\"", s),
+            };
+            let (name, default) = match after[..end].find(":-") {
+                Some(i) => (&after[..i], Some(&after[i + 2..end])),
+                None => (&after[..end], None),
+            };
+            if !is_valid_name(name) {
+                anyhow::bail!(
+                    "Invalid environment variable name \"{}\" in configuration value \"{}\"",
+                    name,
+                    s
+                )
+            }
+
+            match (lookup(name), default) {
+                // like the shell, a default also applies to variables that are set but empty
+                (Some(value), Some(default)) if value.is_empty() => result.push_str(default),
+                (Some(value), _) => result.push_str(&value),
+                (None, Some(default)) => result.push_str(default),
+                (None, None) => {
+                    undefined.insert(name.to_string());
+                }
+            }
+            rest = &after[end + 1..];
+        } else {
+            result.push('$');
+            rest = &rest[1..];
+        }
+    }
+    result.push_str(rest);
+
+    Ok(result)
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c == '_' || c.is_ascii_alphabetic())
+        && chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "ZONE_ID" => Some("samplezoneid".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    fn interpolated(source: &str) -> Result<Value> {
+        let mut value = serde_json::to_value(source.parse::<toml::Value>()?)?;
+        interpolate_with(&mut value, &lookup)?;
+        Ok(value)
+    }
+
+    #[test]
+    fn it_interpolates_nested_strings() {
+        let value = interpolated(
+            r#"
+            zone_id = "${ZONE_ID}"
+            routes = ["${ZONE_ID}.example.com/*"]
+
+            [env.staging.vars]
+            HOST = "${HOST:-staging.example.com}"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(value["zone_id"].as_str(), Some("samplezoneid"));
+        assert_eq!(
+            value["routes"][0].as_str(),
+            Some("samplezoneid.example.com/*")
+        );
+        assert_eq!(
+            value["env"]["staging"]["vars"]["HOST"].as_str(),
+            Some("staging.example.com")
+        );
+    }
+
+    #[test]
+    fn it_uses_defaults_for_empty_variables() {
+        let value = interpolated(r#"a = "${EMPTY:-fallback}""#).unwrap();
+        assert_eq!(value["a"].as_str(), Some("fallback"));
+
+        let value = interpolated(r#"a = "${EMPTY}""#).unwrap();
+        assert_eq!(value["a"].as_str(), Some(""));
+    }
+
+    #[test]
+    fn it_leaves_escaped_and_bare_dollars_alone() {
+        let value = interpolated(r#"a = "$${NOT_A_VAR} costs $5, $$5 or ${ZONE_ID}""#).unwrap();
+        assert_eq!(
+            value["a"].as_str(),
+            Some("${NOT_A_VAR} costs $5, $$5 or samplezoneid")
+        );
+    }
+
+    #[test]
+    fn it_lists_every_undefined_variable() {
+        let error = interpolated(
+            r#"
+            account_id = "${ACCOUNT_ID}"
+            [env.production]
+            zone_id = "${PRODUCTION_ZONE_ID}"
+            route = "${ACCOUNT_ID}.example.com/*"
+            "#,
+        )
+        .unwrap_err()
+        .to_string();
+
+        assert!(
+            error.contains("ACCOUNT_ID, PRODUCTION_ZONE_ID"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn it_rejects_malformed_references() {
+        assert!(interpolated(r#"a = "${ZONE_ID""#).is_err());
+        assert!(interpolated(r#"a = "${1ZONE}""#).is_err());
+    }
+}
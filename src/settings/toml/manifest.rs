@@ -4,15 +4,20 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-use config::{Config, File};
+use config::{Config, File, FileFormat};
 
 use anyhow::{anyhow, Result};
 use chrono::Utc;
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use serde_with::rust::string_empty_as_none;
 
+use super::config_format::ConfigFormat;
+use super::extends::{self, Extended};
+use super::interpolate;
 use super::migrations::{MigrationConfig, MigrationTag, Migrations};
+use super::workspace;
 use super::UsageModel;
 use crate::commands::whoami::fetch_accounts;
 use crate::commands::{validate_worker_name, whoami, DEFAULT_CONFIG_PATH};
@@ -21,10 +26,11 @@ use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::builder::Builder;
 use crate::settings::toml::dev::Dev;
 use crate::settings::toml::durable_objects::DurableObjects;
-use crate::settings::toml::environment::Environment;
+use crate::settings::toml::environment::{Environment, InheritedKey};
 use crate::settings::toml::kv_namespace::{ConfigKvNamespace, KvNamespace};
 use crate::settings::toml::r2_bucket::{ConfigR2Bucket, R2Bucket};
-use crate::settings::toml::route::RouteConfig;
+use crate::settings::toml::route::{ConfigRoute, RouteConfig};
+use crate::settings::toml::service::ServiceBinding;
 use crate::settings::toml::site::Site;
 use crate::settings::toml::target_type::TargetType;
 use crate::settings::toml::triggers::Triggers;
@@ -46,9 +52,10 @@ pub struct Manifest {
     pub workers_dev: Option<bool>,
     #[serde(default, with = "string_empty_as_none")]
     pub route: Option<String>,
-    pub routes: Option<Vec<String>>,
+    pub routes: Option<Vec<ConfigRoute>>,
     #[serde(default, with = "string_empty_as_none")]
     pub zone_id: Option<String>,
+    pub custom_domains: Option<Vec<String>>,
     pub webpack_config: Option<String>,
     pub build: Option<Builder>,
     pub private: Option<bool>,
@@ -65,10 +72,12 @@ pub struct Manifest {
     #[serde(alias = "kv-namespaces")]
     pub kv_namespaces: Option<Vec<ConfigKvNamespace>>,
     pub r2_buckets: Option<Vec<ConfigR2Bucket>>,
+    pub services: Option<Vec<ServiceBinding>>,
     // TODO: maybe one day, serde toml support will allow us to serialize sites
     // as a TOML inline table (this would prevent confusion with environments too!)
     pub site: Option<Site>,
-    pub vars: Option<HashMap<String, String>>,
+    pub vars: Option<HashMap<String, Value>>,
+    pub secrets: Option<Vec<String>>,
     pub text_blobs: Option<HashMap<String, PathBuf>>,
     pub wasm_modules: Option<HashMap<String, PathBuf>>,
 }
@@ -81,8 +90,25 @@ impl Manifest {
             message.push_str("; run `wrangler init` to create one.");
         }
         anyhow::ensure!(config_path.exists(), message);
-        let config = read_config(config_path)?;
+        let (config, extended) = read_config(config_path)?;
+
+        if config.get::<Value>("workspace").is_ok() {
+            anyhow::bail!(
+                "{} is a workspace; run this command in one of its members' directories, or pick members with --worker <name> or --all (supported by {})",
+                file_name,
+                workspace::member_commands()
+            )
+        }
+
+        Manifest::from_config(config).map_err(|e| match &extended {
+            Some(extended) => extended.explain(e),
+            None => e,
+        })
+    }
 
+    /// Deserializes an already merged configuration, as read by
+    /// [`Manifest::new`] or assembled for a workspace member.
+    pub(super) fn from_config(config: Config) -> Result<Self> {
         let manifest: Manifest = match config.try_into() {
             Ok(m) => m,
             Err(e) => {
@@ -104,9 +130,12 @@ impl Manifest {
         target_type: Option<TargetType>,
         config_path: &Path,
         site: Option<Site>,
+        format: ConfigFormat,
     ) -> Result<Manifest> {
-        let config_file = &config_path.join("wrangler.toml");
-        let config_template_str = fs::read_to_string(config_file).unwrap_or_else(|err| {
+        // templates always come with a wrangler.toml, whatever format we write
+        let template_file = &config_path.join(ConfigFormat::Toml.file_name());
+        let config_file = &config_path.join(format.file_name());
+        let config_template_str = fs::read_to_string(template_file).unwrap_or_else(|err| {
             log::info!("Error reading config template: {}", err);
             log::info!("Using default instead");
             toml::to_string_pretty(&Manifest::default())
@@ -183,8 +212,15 @@ impl Manifest {
         let toml = config_template_doc.to_string_in_original_order();
         let manifest = toml::from_str::<Manifest>(&toml)?;
 
-        log::info!("Writing a wrangler.toml file at {}", config_file.display());
-        fs::write(&config_file, &toml)?;
+        log::info!(
+            "Writing a {} file at {}",
+            format.file_name(),
+            config_file.display()
+        );
+        fs::write(&config_file, format.render(&toml)?)?;
+        if format != ConfigFormat::Toml && template_file.exists() {
+            fs::remove_file(template_file)?;
+        }
         Ok(manifest)
     }
 
@@ -223,7 +259,7 @@ impl Manifest {
             if route_config.is_zoned() {
                 let zoned = deploy::ZonedTarget::build(&script, route_config)?;
 
-                if zoned.routes.is_empty() {
+                if zoned.is_empty() {
                     return Ok(());
                 }
 
@@ -231,8 +267,8 @@ impl Manifest {
                 // the user that their site may not work as expected without it.
                 if self.site.is_some() {
                     let no_star_routes = zoned
-                        .routes
                         .iter()
+                        .flat_map(|z| z.routes.iter())
                         .filter(|r| !r.pattern.ends_with('*'))
                         .map(|r| r.pattern.as_str())
                         .collect::<Vec<_>>();
@@ -244,7 +280,7 @@ impl Manifest {
                     }
                 }
 
-                deployments.push(DeployTarget::Zoned(zoned));
+                deployments.extend(zoned.into_iter().map(DeployTarget::Zoned));
             }
 
             if route_config.is_zoneless() {
@@ -285,6 +321,31 @@ impl Manifest {
             add_routed_deployments(&self.route_config())
         }?;
 
+        // like routes, custom domains aren't inherited by environments
+        let custom_domains: Option<(&Vec<String>, LazyAccountId)> = match env {
+            Some(e) => e.custom_domains.as_ref().map(|hostnames| {
+                let account_id = e
+                    .account_id
+                    .clone()
+                    .or_else(|| self.account_id.if_present().cloned());
+                (hostnames, account_id.into())
+            }),
+            None => self
+                .custom_domains
+                .as_ref()
+                .map(|hostnames| (hostnames, self.account_id.clone())),
+        };
+
+        if let Some((hostnames, account_id)) = custom_domains {
+            if !hostnames.is_empty() {
+                deployments.push(DeployTarget::CustomDomain(deploy::CustomDomainTarget {
+                    account_id,
+                    script_name: script.clone(),
+                    hostnames: hostnames.clone(),
+                }));
+            }
+        }
+
         let crons = match env {
             Some(e) => {
                 let account_id = match e.account_id.as_ref() {
@@ -312,8 +373,8 @@ impl Manifest {
         }
 
         let durable_objects = match env {
-            Some(e) => e.durable_objects.as_ref(),
-            None => self.durable_objects.as_ref(),
+            Some(e) => self.environment_durable_objects(e),
+            None => self.durable_objects.clone(),
         };
 
         if durable_objects.is_none() && deployments.is_empty() {
@@ -377,6 +438,7 @@ impl Manifest {
             name: self.name.clone(), // Inherited
             kv_namespaces: get_namespaces(self.kv_namespaces.clone(), preview)?, // Not inherited
             r2_buckets: get_buckets(self.r2_buckets.clone(), preview)?, // Not inherited
+            services: self.services.clone().unwrap_or_default(), // Not inherited
             durable_objects: self.durable_objects.clone(), // Not inherited
             migrations: match (preview, &self.migrations) {
                 (false, Some(migrations)) => Some(Migrations::List {
@@ -384,14 +446,15 @@ impl Manifest {
                     migrations: migrations.clone(),
                 }),
                 _ => None,
-            }, // Top level
+            }, // Inherited
             site: self.site.clone(), // Inherited
             vars: self.vars.clone(), // Not inherited
+            secrets: self.secrets.clone(), // Inherited
             text_blobs: self.text_blobs.clone(), // Inherited
-            usage_model: self.usage_model, // Top level
-            wasm_modules: self.wasm_modules.clone(),
-            compatibility_date: self.compatibility_date.clone(),
-            compatibility_flags: self.compatibility_flags.clone(),
+            usage_model: self.usage_model, // Inherited
+            wasm_modules: self.wasm_modules.clone(), // Inherited
+            compatibility_date: self.compatibility_date.clone(), // Inherited
+            compatibility_flags: self.compatibility_flags.clone(), // Inherited
         };
 
         let environment = self.get_environment(environment_name)?;
@@ -407,28 +470,110 @@ impl Manifest {
             if let Some(build) = &environment.build {
                 target.build = Some(build.clone());
             }
+            if let Some(usage_model) = environment.usage_model {
+                target.usage_model = Some(usage_model);
+            }
+            if let Some(wasm_modules) = &environment.wasm_modules {
+                target.wasm_modules = Some(wasm_modules.clone());
+            }
+            if let Some(compatibility_date) = &environment.compatibility_date {
+                target.compatibility_date = Some(compatibility_date.clone());
+            }
+            if let Some(secrets) = &environment.secrets {
+                target.secrets = Some(secrets.clone());
+            }
+            if let Some(compatibility_flags) = &environment.compatibility_flags {
+                target.compatibility_flags = compatibility_flags.clone();
+            }
+            if let (false, Some(migrations)) = (preview, &environment.migrations) {
+                target.migrations = Some(Migrations::List {
+                    script_tag: MigrationTag::Unknown,
+                    migrations: migrations.clone(),
+                });
+            }
 
-            // don't inherit kv namespaces because it is an anti-pattern to use the same namespaces across multiple environments
-            target.kv_namespaces = get_namespaces(environment.kv_namespaces.clone(), preview)?;
-
-            // don't inherit r2 buckets because it is an anti-pattern to use the same buckets across multiple environments
-            target.r2_buckets = get_buckets(environment.r2_buckets.clone(), preview)?;
-
-            // don't inherit durable object configuration
-            target.durable_objects = environment.durable_objects.clone();
+            // don't inherit kv namespaces because it is an anti-pattern to use the same namespaces across multiple environments,
+            // unless the environment explicitly opts in to it
+            target.kv_namespaces = get_namespaces(
+                merge_bindings(
+                    environment.inherits(InheritedKey::KvNamespaces),
+                    &self.kv_namespaces,
+                    &environment.kv_namespaces,
+                    |ns| &ns.binding,
+                ),
+                preview,
+            )?;
+
+            // don't inherit r2 buckets because it is an anti-pattern to use the same buckets across multiple environments,
+            // unless the environment explicitly opts in to it
+            target.r2_buckets = get_buckets(
+                merge_bindings(
+                    environment.inherits(InheritedKey::R2Buckets),
+                    &self.r2_buckets,
+                    &environment.r2_buckets,
+                    |bucket| &bucket.binding,
+                ),
+                preview,
+            )?;
+
+            // don't inherit service bindings unless opted in, since the services
+            // an environment talks to usually differ too
+            target.services = merge_bindings(
+                environment.inherits(InheritedKey::Services),
+                &self.services,
+                &environment.services,
+                |service| &service.binding,
+            )
+            .unwrap_or_default();
+
+            // don't inherit durable object configuration unless opted in
+            target.durable_objects = self.environment_durable_objects(environment);
 
             // inherit site configuration
             if let Some(site) = &environment.site {
                 target.site = Some(site.clone());
             }
 
-            // don't inherit vars
-            target.vars = environment.vars.clone();
+            // don't inherit vars unless opted in, in which case the environment's vars
+            // override top level vars with the same name
+            target.vars = if environment.inherits(InheritedKey::Vars) {
+                match (&self.vars, &environment.vars) {
+                    (Some(top_level), Some(vars)) => {
+                        let mut merged = top_level.clone();
+                        merged.extend(vars.clone());
+                        Some(merged)
+                    }
+                    (top_level, vars) => vars.clone().or_else(|| top_level.clone()),
+                }
+            } else {
+                environment.vars.clone()
+            };
         }
 
         Ok(target)
     }
 
+    fn environment_durable_objects(&self, environment: &Environment) -> Option<DurableObjects> {
+        if !environment.inherits(InheritedKey::DurableObjects) {
+            return environment.durable_objects.clone();
+        }
+
+        let top_level = self.durable_objects.as_ref().map(|d| &d.classes);
+        let own = environment.durable_objects.as_ref().map(|d| &d.classes);
+        let classes = merge_bindings(
+            true,
+            top_level.unwrap_or(&None),
+            own.unwrap_or(&None),
+            |class| &class.binding,
+        );
+
+        if self.durable_objects.is_none() && environment.durable_objects.is_none() {
+            None
+        } else {
+            Some(DurableObjects { classes })
+        }
+    }
+
     pub fn get_environment(&self, environment_name: Option<&str>) -> Result<Option<&Environment>> {
         // check for user-specified environment name
         if let Some(environment_name) = environment_name {
@@ -672,17 +817,49 @@ impl FromStr for Manifest {
     }
 }
 
-fn read_config(config_path: &Path) -> Result<Config> {
+/// Reads a configuration file, along with the files it extends, if any.
+fn read_config(config_path: &Path) -> Result<(Config, Option<Extended>)> {
     let mut config = Config::new();
 
     let config_str = config_path
         .to_str()
         .expect("project config path should be a string");
-    config.merge(File::with_name(config_str))?;
+
+    let source = fs::read_to_string(config_path)?;
+    let extended = if extends::is_extending(config_path, &source) {
+        Some(extends::load(config_path)?)
+    } else {
+        None
+    };
+
+    if let Some(extended) = &extended {
+        let mut value = extended.value.clone();
+        if interpolate::enabled() {
+            interpolate::interpolate(&mut value)?;
+        }
+        config.merge(File::from_str(&value.to_string(), FileFormat::Json))?;
+    } else if interpolate::enabled() && source.contains("${") {
+        let mut value = ConfigFormat::from_path(config_path).parse(&source)?;
+        interpolate::interpolate(&mut value)?;
+        config.merge(File::from_str(&value.to_string(), FileFormat::Json))?;
+    } else {
+        // the format is inferred from the file extension
+        config.merge(File::with_name(config_str))?;
+    }
 
     // Eg.. `CF_ACCOUNT_AUTH_KEY=farts` would set the `account_auth_key` key
     config.merge(config::Environment::with_prefix("CF"))?;
 
+    Ok((config, extended))
+}
+
+/// Builds a configuration from an already parsed value, applying the same `CF_`
+/// environment overrides as [`read_config`].
+pub(super) fn config_from_value(value: &Value) -> Result<Config> {
+    let mut config = Config::new();
+    config.merge(File::from_str(&value.to_string(), FileFormat::Json))?;
+    config.merge(config::Environment::with_prefix("CF"))?;
+
     Ok(config)
 }
 
@@ -722,6 +899,29 @@ fn check_for_duplicate_names(manifest: &Manifest) -> Result<()> {
     Ok(())
 }
 
+// Combine top level bindings with an environment's own, if the environment opted in
+// to inheriting them. Bindings defined by the environment replace top level bindings
+// with the same name.
+fn merge_bindings<T: Clone>(
+    inherit: bool,
+    top_level: &Option<Vec<T>>,
+    own: &Option<Vec<T>>,
+    binding: impl Fn(&T) -> &String,
+) -> Option<Vec<T>> {
+    match (inherit, top_level, own) {
+        (true, Some(top_level), Some(own)) => Some(
+            top_level
+                .iter()
+                .filter(|inherited| !own.iter().any(|b| binding(b) == binding(inherited)))
+                .chain(own.iter())
+                .cloned()
+                .collect(),
+        ),
+        (true, Some(top_level), None) => Some(top_level.clone()),
+        _ => own.clone(),
+    }
+}
+
 fn get_namespaces(
     kv_namespaces: Option<Vec<ConfigKvNamespace>>,
     preview: bool,
@@ -800,18 +1000,48 @@ mod tests {
             Some(TargetType::JavaScript),
             toml_path,
             None,
+            ConfigFormat::Toml,
         )?;
         assert_eq!(toml.name, "test".to_string());
         assert_eq!(toml.target_type.to_string(), "javascript".to_string());
         fs::remove_file(toml_path.with_file_name("wrangler.toml"))?;
 
-        let toml = Manifest::generate("test".to_string(), None, toml_path, None)?;
+        let toml = Manifest::generate(
+            "test".to_string(),
+            None,
+            toml_path,
+            None,
+            ConfigFormat::Toml,
+        )?;
         assert_eq!(toml.target_type.to_string(), "webpack".to_string());
         fs::remove_file(toml_path.with_file_name("wrangler.toml"))?;
 
         Ok(())
     }
 
+    #[test]
+    fn generate_json_and_yaml() -> Result<()> {
+        for format in &[ConfigFormat::Json, ConfigFormat::Yaml] {
+            let dir = tempfile::tempdir()?;
+            let generated = Manifest::generate(
+                "test".to_string(),
+                Some(TargetType::JavaScript),
+                dir.path(),
+                None,
+                *format,
+            )?;
+
+            let config_path = dir.path().join(format.file_name());
+            assert!(!dir.path().join("wrangler.toml").exists());
+            let manifest = Manifest::new(&config_path)?;
+            assert_eq!(manifest.name, generated.name);
+            assert_eq!(manifest.target_type, generated.target_type);
+            assert_eq!(manifest.compatibility_date, generated.compatibility_date);
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn serialize() {
         let manifest = Manifest {
@@ -823,7 +1053,7 @@ mod tests {
             }]),
             site: Some(Default::default()),
             vars: Some(
-                vec![("FOO".to_string(), "some value".to_string())]
+                vec![("FOO".to_string(), Value::from("some value"))]
                     .into_iter()
                     .collect(),
             ),
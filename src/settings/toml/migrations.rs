@@ -154,6 +154,77 @@ impl Migrations {
             }
         }
     }
+
+    /// Check that the migrations already applied to the script, as recorded
+    /// when they were published, still open the list of migrations unchanged.
+    /// Applying a list whose history was edited would send the wrong steps.
+    pub fn verify_history(&self, applied: &[MigrationConfig]) -> Result<(), anyhow::Error> {
+        let (script_tag, migrations) = match self {
+            Migrations::List {
+                script_tag: MigrationTag::HasTag(script_tag),
+                migrations,
+            } => (script_tag, migrations),
+            _ => return Ok(()),
+        };
+
+        // the record only covers the script if it reaches the script's current tag;
+        // otherwise the script was last published from somewhere else
+        let applied = match applied.iter().position(|m| &m.tag == script_tag) {
+            Some(position) => &applied[..=position],
+            None => {
+                log::info!(
+                    "No record of the migrations applied up to \"{}\", skipping history check",
+                    script_tag
+                );
+                return Ok(());
+            }
+        };
+
+        for (index, applied) in applied.iter().enumerate() {
+            let diverged = match migrations.get(index) {
+                Some(migration) if migration == applied => continue,
+                Some(migration) if migration.tag == applied.tag => format!(
+                    "The migration \"{}\" was changed after it was applied. It was applied as:\n{}\nbut is now:\n{}",
+                    applied.tag,
+                    describe_migration(applied),
+                    describe_migration(migration)
+                ),
+                _ => match migrations.iter().position(|m| m.tag == applied.tag) {
+                    Some(position) => format!(
+                        "The migration \"{}\" was applied as migration #{} but is now #{}; migrations that have been applied can't be reordered",
+                        applied.tag,
+                        index + 1,
+                        position + 1
+                    ),
+                    None => format!(
+                        "The migration \"{}\" (#{}) was applied but has been removed from your wrangler.toml",
+                        applied.tag,
+                        index + 1
+                    ),
+                },
+            };
+
+            anyhow::bail!(
+                "{}\nMigrations up to the script's current tag \"{}\" have already been applied and must not be edited. Restore them, and add a new migration for any further changes.",
+                diverged,
+                script_tag
+            )
+        }
+
+        Ok(())
+    }
+
+    /// The migrations that will have been applied once this list is published.
+    pub fn applied_after_publish(&self) -> Option<&[MigrationConfig]> {
+        match self {
+            Migrations::List { migrations, .. } => Some(migrations),
+            Migrations::Adhoc { .. } => None,
+        }
+    }
+}
+
+fn describe_migration(migration: &MigrationConfig) -> String {
+    serde_json::to_string(&migration.migration).unwrap_or_default()
 }
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
@@ -172,6 +243,20 @@ pub struct ApiMigration {
     steps: Vec<Migration>,
 }
 
+impl ApiMigration {
+    pub fn old_tag(&self) -> Option<&str> {
+        self.old_tag.as_deref()
+    }
+
+    pub fn new_tag(&self) -> Option<&str> {
+        self.new_tag.as_deref()
+    }
+
+    pub fn steps(&self) -> &[Migration] {
+        &self.steps
+    }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 pub struct Migration {
     #[serde(flatten)]
@@ -190,6 +275,13 @@ pub struct DurableObjectsMigration {
     pub transferred_classes: Vec<TransferClass>,
 }
 
+impl DurableObjectsMigration {
+    // deleting a class deletes every durable object of that class, along with its storage
+    pub fn is_destructive(&self) -> bool {
+        !self.deleted_classes.is_empty()
+    }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 pub struct RenameClass {
     pub from: String,
@@ -376,4 +468,77 @@ mod tests {
 
         Ok(())
     }
+
+    fn new_class(tag: &str, class: &str) -> MigrationConfig {
+        MigrationConfig {
+            tag: String::from(tag),
+            migration: Migration {
+                durable_objects: DurableObjectsMigration {
+                    new_classes: vec![String::from(class)],
+                    ..Default::default()
+                },
+            },
+        }
+    }
+
+    fn list_at(tag: &str, migrations: Vec<MigrationConfig>) -> Migrations {
+        Migrations::List {
+            script_tag: MigrationTag::HasTag(String::from(tag)),
+            migrations,
+        }
+    }
+
+    #[test]
+    fn history_unchanged() -> Result<(), anyhow::Error> {
+        let applied = vec![new_class("v1", "A"), new_class("v2", "B")];
+        let migrations = list_at(
+            "v2",
+            vec![
+                new_class("v1", "A"),
+                new_class("v2", "B"),
+                new_class("v3", "C"),
+            ],
+        );
+
+        migrations.verify_history(&applied)
+    }
+
+    #[test]
+    fn history_edited() {
+        let applied = vec![new_class("v1", "A"), new_class("v2", "B")];
+        let migrations = list_at("v2", vec![new_class("v1", "A"), new_class("v2", "Renamed")]);
+
+        let error = migrations.verify_history(&applied).unwrap_err().to_string();
+        assert!(error.contains("\"v2\" was changed"), "{}", error);
+    }
+
+    #[test]
+    fn history_reordered_or_removed() {
+        let applied = vec![new_class("v1", "A"), new_class("v2", "B")];
+
+        let reordered = list_at("v2", vec![new_class("v2", "B"), new_class("v1", "A")]);
+        let error = reordered.verify_history(&applied).unwrap_err().to_string();
+        assert!(
+            error.contains("\"v1\" was applied as migration #1 but is now #2"),
+            "{}",
+            error
+        );
+
+        let removed = list_at("v2", vec![new_class("v2", "B")]);
+        let error = removed.verify_history(&applied).unwrap_err().to_string();
+        assert!(
+            error.contains("\"v1\" (#1) was applied but has been removed"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn history_ignored_when_out_of_date() -> Result<(), anyhow::Error> {
+        // the script was last published from somewhere without this record
+        let applied = vec![new_class("v1", "A")];
+        let migrations = list_at("v2", vec![new_class("v1", "Edited"), new_class("v2", "B")]);
+
+        migrations.verify_history(&applied)
+    }
 }
@@ -1,26 +1,37 @@
 mod builder;
+mod config_format;
 mod dev;
+pub mod diagnostics;
 mod durable_objects;
 mod environment;
+mod extends;
+mod interpolate;
 mod kv_namespace;
 mod manifest;
 pub mod migrations;
 mod r2_bucket;
 mod route;
+pub mod schema;
+mod service;
 mod site;
 pub(crate) mod target;
 mod target_type;
 mod triggers;
+mod workspace;
 
 pub use builder::{ModuleRule, UploadFormat};
+pub use config_format::ConfigFormat;
 pub use durable_objects::{DurableObjects, DurableObjectsClass};
+pub use environment::InheritedKey;
 pub use kv_namespace::{ConfigKvNamespace, KvNamespace};
 pub use manifest::Manifest;
 pub use r2_bucket::{ConfigR2Bucket, R2Bucket};
-pub use route::{Route, RouteConfig};
+pub use route::{ConfigRoute, Route, RouteConfig, ZonedRoute};
+pub use service::ServiceBinding;
 pub use site::Site;
 pub use target::Target;
 pub use target_type::TargetType;
+pub use workspace::{member_commands, Member, Workspace, WorkspaceConfig, MEMBER_COMMANDS};
 
 use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
@@ -1,8 +1,10 @@
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use cloudflare::endpoints::workers::WorkersRoute;
 
 use super::manifest::LazyAccountId;
+use crate::deploy::Zone;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Route {
@@ -21,11 +23,78 @@ impl From<&WorkersRoute> for Route {
     }
 }
 
+/// An entry of `routes`: a bare pattern on the configuration's `zone_id`, or a
+/// table giving the pattern its own zone.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ConfigRoute {
+    Pattern(String),
+    Zoned(ZonedRoute),
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ZonedRoute {
+    pub pattern: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone_name: Option<String>,
+}
+
+impl ConfigRoute {
+    pub fn pattern(&self) -> &str {
+        match self {
+            ConfigRoute::Pattern(pattern) => pattern,
+            ConfigRoute::Zoned(route) => &route.pattern,
+        }
+    }
+
+    /// The zone the route names itself, if any.
+    pub fn zone(&self) -> Result<Option<Zone>> {
+        match self {
+            ConfigRoute::Pattern(_) => Ok(None),
+            ConfigRoute::Zoned(ZonedRoute {
+                pattern,
+                zone_id: Some(_),
+                zone_name: Some(_),
+            }) => anyhow::bail!(
+                "the route {} sets both `zone_id` and `zone_name`; set only one",
+                pattern
+            ),
+            ConfigRoute::Zoned(route) => Ok(route
+                .zone_id
+                .clone()
+                .map(Zone::Id)
+                .or_else(|| route.zone_name.clone().map(Zone::Name))),
+        }
+    }
+
+    fn has_zone(&self) -> bool {
+        matches!(
+            self,
+            ConfigRoute::Zoned(ZonedRoute {
+                zone_id: Some(_),
+                ..
+            }) | ConfigRoute::Zoned(ZonedRoute {
+                zone_name: Some(_),
+                ..
+            })
+        )
+    }
+}
+
+impl From<&str> for ConfigRoute {
+    fn from(pattern: &str) -> Self {
+        ConfigRoute::Pattern(pattern.to_string())
+    }
+}
+
 #[derive(Debug)]
 pub struct RouteConfig {
     pub workers_dev: Option<bool>,
     pub route: Option<String>,
-    pub routes: Option<Vec<String>>,
+    pub routes: Option<Vec<ConfigRoute>>,
     pub zone_id: Option<String>,
     pub account_id: LazyAccountId,
 }
@@ -46,6 +115,7 @@ impl RouteConfig {
     }
 
     pub fn is_zoned(&self) -> bool {
-        self.has_routes_defined() && self.zone_id.is_some()
+        self.has_routes_defined()
+            && (self.zone_id.is_some() || self.routes.iter().flatten().any(ConfigRoute::has_zone))
     }
 }
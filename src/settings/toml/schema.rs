@@ -0,0 +1,502 @@
+//! A JSON Schema describing wrangler.toml, for editors and pre-commit hooks.
+//!
+//! The schema is written out by hand next to the `Manifest` types; the tests at
+//! the bottom of this file fail if a serde field is added to one of those types
+//! without also being described here.
+
+use serde_json::{json, Map, Value};
+
+use crate::upload::form::ModuleType;
+
+const SCHEMA_URL: &str = "http://json-schema.org/draft-07/schema#";
+
+/// Build the JSON Schema for a wrangler.toml file.
+pub fn schema() -> Value {
+    let mut properties = worker_properties();
+    properties.extend(object(vec![
+        (
+            "type",
+            json!({
+                "description": "The type of project to build",
+                "enum": ["javascript", "rust", "webpack"]
+            }),
+        ),
+        ("dev", reference("Dev")),
+        ("workspace", reference("Workspace")),
+        (
+            "extends",
+            json!({
+                "description": "Configuration files to start from, relative to this one; later files and this file take precedence",
+                "oneOf": [string(), array(string())]
+            }),
+        ),
+        (
+            "env",
+            json!({
+                "description": "Environments that override the top level configuration, keyed by name",
+                "type": "object",
+                "additionalProperties": reference("Environment")
+            }),
+        ),
+    ]));
+
+    json!({
+        "$schema": SCHEMA_URL,
+        "title": "wrangler.toml",
+        "description": "Configuration for a Cloudflare Workers project",
+        "type": "object",
+        // a workspace root only holds settings shared by its members, and
+        // `type` may come from an extended file
+        "anyOf": [
+            { "required": ["type"] },
+            { "required": ["workspace"] },
+            { "required": ["extends"] }
+        ],
+        "properties": properties,
+        "definitions": definitions()
+    })
+}
+
+// keys that can be set both at the top level and in an environment
+fn worker_properties() -> Map<String, Value> {
+    object(vec![
+        (
+            "name",
+            json!({ "type": "string", "description": "The name of your Worker" }),
+        ),
+        ("account_id", string()),
+        (
+            "workers_dev",
+            json!({ "type": "boolean", "description": "Whether to publish to your workers.dev subdomain" }),
+        ),
+        ("route", string()),
+        (
+            "routes",
+            array(json!({ "oneOf": [string(), reference("ZonedRoute")] })),
+        ),
+        ("zone_id", string()),
+        ("custom_domains", array(string())),
+        ("webpack_config", string()),
+        ("build", reference("Builder")),
+        ("private", json!({ "type": "boolean" })),
+        ("site", reference("Site")),
+        ("kv_namespaces", array(reference("ConfigKvNamespace"))),
+        ("kv-namespaces", array(reference("ConfigKvNamespace"))),
+        ("r2_buckets", array(reference("ConfigR2Bucket"))),
+        ("services", array(reference("ServiceBinding"))),
+        (
+            "vars",
+            map(json!({ "type": ["string", "number", "boolean", "array", "object"] })),
+        ),
+        ("secrets", array(string())),
+        ("text_blobs", map(string())),
+        ("wasm_modules", map(string())),
+        ("triggers", reference("Triggers")),
+        ("durable_objects", reference("DurableObjects")),
+        ("migrations", array(reference("MigrationConfig"))),
+        ("usage_model", json!({ "enum": ["bundled", "unbound", ""] })),
+        (
+            "compatibility_date",
+            json!({ "type": "string", "pattern": "^[0-9]{4}-[0-9]{2}-[0-9]{2}$" }),
+        ),
+        ("compatibility_flags", array(string())),
+    ])
+}
+
+fn definitions() -> Map<String, Value> {
+    let mut environment = worker_properties();
+    environment.insert(
+        "inherit".to_string(),
+        json!({
+            "description": "Top level keys that this environment inherits in addition to its own",
+            "type": "array",
+            "items": {
+                "enum": ["vars", "durable_objects", "kv_namespaces", "kv-namespaces", "r2_buckets", "services"]
+            },
+            "uniqueItems": true
+        }),
+    );
+
+    object(vec![
+        (
+            "Environment",
+            json!({ "type": "object", "properties": environment }),
+        ),
+        (
+            "Builder",
+            strict(
+                vec!["upload"],
+                vec![
+                    (
+                        "command",
+                        json!({ "type": "string", "description": "The command used to build your Worker" }),
+                    ),
+                    (
+                        "cwd",
+                        json!({ "type": "string", "description": "The directory the build command runs in" }),
+                    ),
+                    (
+                        "watch_dir",
+                        json!({ "type": "string", "description": "The directory watched for changes by `wrangler dev`" }),
+                    ),
+                    ("upload", reference("UploadFormat")),
+                ],
+            ),
+        ),
+        (
+            "UploadFormat",
+            json!({
+                "oneOf": [
+                    strict(vec!["format"], vec![("format", json!({ "const": "service-worker" }))]),
+                    strict(
+                        vec!["format", "main"],
+                        vec![
+                            ("format", json!({ "const": "modules" })),
+                            (
+                                "main",
+                                json!({ "type": "string", "description": "The name of the module that is your Worker's entry point" }),
+                            ),
+                            ("dir", string()),
+                            ("rules", array(reference("ModuleRule"))),
+                        ],
+                    ),
+                ]
+            }),
+        ),
+        (
+            "ModuleRule",
+            strict(
+                vec!["globs", "type"],
+                vec![
+                    ("globs", array(string())),
+                    ("type", json!({ "enum": module_types() })),
+                    ("fallthrough", json!({ "type": "boolean" })),
+                ],
+            ),
+        ),
+        (
+            "Site",
+            strict(
+                vec!["bucket"],
+                vec![
+                    (
+                        "bucket",
+                        json!({ "type": "string", "description": "The directory containing your static assets" }),
+                    ),
+                    ("entry-point", string()),
+                    ("include", array(string())),
+                    ("exclude", array(string())),
+                ],
+            ),
+        ),
+        (
+            "Dev",
+            strict(
+                vec![],
+                vec![
+                    ("ip", string()),
+                    (
+                        "port",
+                        json!({ "type": "integer", "minimum": 0, "maximum": 65535 }),
+                    ),
+                    ("local_protocol", json!({ "enum": ["http", "https"] })),
+                    ("upstream_protocol", json!({ "enum": ["http", "https"] })),
+                ],
+            ),
+        ),
+        (
+            "Triggers",
+            table(vec!["crons"], vec![("crons", array(string()))]),
+        ),
+        (
+            "DurableObjects",
+            table(
+                vec![],
+                vec![
+                    ("classes", array(reference("DurableObjectsClass"))),
+                    ("bindings", array(reference("DurableObjectsClass"))),
+                ],
+            ),
+        ),
+        (
+            "DurableObjectsClass",
+            table(
+                vec!["class_name"],
+                vec![
+                    ("binding", string()),
+                    ("name", string()),
+                    ("class_name", string()),
+                    ("script_name", string()),
+                ],
+            ),
+        ),
+        (
+            "MigrationConfig",
+            table(
+                vec!["tag"],
+                vec![
+                    ("tag", string()),
+                    ("new_classes", array(string())),
+                    ("deleted_classes", array(string())),
+                    ("renamed_classes", array(reference("RenameClass"))),
+                    ("transferred_classes", array(reference("TransferClass"))),
+                ],
+            ),
+        ),
+        (
+            "RenameClass",
+            table(
+                vec!["from", "to"],
+                vec![("from", string()), ("to", string())],
+            ),
+        ),
+        (
+            "TransferClass",
+            table(
+                vec!["from", "from_script", "to"],
+                vec![
+                    ("from", string()),
+                    ("from_script", string()),
+                    ("to", string()),
+                ],
+            ),
+        ),
+        (
+            "ConfigKvNamespace",
+            table(
+                vec!["binding"],
+                vec![
+                    ("binding", string()),
+                    ("id", string()),
+                    ("preview_id", string()),
+                ],
+            ),
+        ),
+        (
+            "ConfigR2Bucket",
+            table(
+                vec!["binding"],
+                vec![
+                    ("binding", string()),
+                    ("bucket_name", string()),
+                    ("preview_bucket_name", string()),
+                ],
+            ),
+        ),
+        (
+            "ServiceBinding",
+            table(
+                vec!["binding", "service"],
+                vec![
+                    ("binding", string()),
+                    ("service", string()),
+                    ("environment", string()),
+                ],
+            ),
+        ),
+        (
+            "ZonedRoute",
+            strict(
+                vec!["pattern"],
+                vec![
+                    ("pattern", string()),
+                    ("zone_id", string()),
+                    ("zone_name", string()),
+                ],
+            ),
+        ),
+        (
+            "Workspace",
+            strict(vec!["members"], vec![("members", array(string()))]),
+        ),
+    ])
+}
+
+fn module_types() -> Vec<&'static str> {
+    ModuleType::iter().map(ModuleType::name).collect()
+}
+
+fn object(entries: Vec<(&str, Value)>) -> Map<String, Value> {
+    entries
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect()
+}
+
+// a table that ignores unknown keys
+fn table(required: Vec<&str>, properties: Vec<(&str, Value)>) -> Value {
+    json!({
+        "type": "object",
+        "required": required,
+        "properties": object(properties)
+    })
+}
+
+// a table that rejects unknown keys, i.e. `#[serde(deny_unknown_fields)]`
+fn strict(required: Vec<&str>, properties: Vec<(&str, Value)>) -> Value {
+    let mut table = table(required, properties);
+    table["additionalProperties"] = json!(false);
+    table
+}
+
+fn reference(definition: &str) -> Value {
+    json!({ "$ref": format!("#/definitions/{}", definition) })
+}
+
+fn string() -> Value {
+    json!({ "type": "string" })
+}
+
+fn array(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+fn map(values: Value) -> Value {
+    json!({ "type": "object", "additionalProperties": values })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde::de::{self, Deserialize, Deserializer, Visitor};
+    use serde::forward_to_deserialize_any;
+
+    use crate::settings::toml::builder::{Builder, ModuleRule};
+    use crate::settings::toml::dev::Dev;
+    use crate::settings::toml::durable_objects::{DurableObjects, DurableObjectsClass};
+    use crate::settings::toml::environment::Environment;
+    use crate::settings::toml::kv_namespace::ConfigKvNamespace;
+    use crate::settings::toml::manifest::Manifest;
+    use crate::settings::toml::migrations::{DurableObjectsMigration, RenameClass, TransferClass};
+    use crate::settings::toml::r2_bucket::ConfigR2Bucket;
+    use crate::settings::toml::route::ZonedRoute;
+    use crate::settings::toml::service::ServiceBinding;
+    use crate::settings::toml::site::Site;
+    use crate::settings::toml::triggers::Triggers;
+    use crate::settings::toml::workspace::WorkspaceConfig;
+
+    // A deserializer that records the field names a derived `Deserialize` impl
+    // asks for, without deserializing anything.
+    struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de, 'a> Deserializer<'de> for FieldNames<'a> {
+        type Error = de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+            Err(de::Error::custom("expected a struct"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            fields: &'static [&'static str],
+            _visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = fields;
+            Err(de::Error::custom("recorded fields"))
+        }
+
+        forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
+            byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map enum identifier ignored_any
+        }
+    }
+
+    fn serde_fields<'de, T: Deserialize<'de>>() -> &'static [&'static str] {
+        let mut fields: &'static [&'static str] = &[];
+        let _ = T::deserialize(FieldNames(&mut fields));
+        assert!(!fields.is_empty(), "could not read serde fields");
+        fields
+    }
+
+    fn assert_covered(fields: &[&str], schema: &Value) {
+        let properties = schema["properties"].as_object().unwrap();
+        for field in fields {
+            assert!(
+                properties.contains_key(*field),
+                "`{}` is missing from the schema",
+                field
+            );
+        }
+    }
+
+    #[test]
+    fn it_covers_every_manifest_field() {
+        let schema = schema();
+        let definitions = &schema["definitions"];
+
+        assert_covered(serde_fields::<Manifest>(), &schema);
+        assert_covered(serde_fields::<Environment>(), &definitions["Environment"]);
+        assert_covered(serde_fields::<Builder>(), &definitions["Builder"]);
+        assert_covered(serde_fields::<ModuleRule>(), &definitions["ModuleRule"]);
+        assert_covered(serde_fields::<Site>(), &definitions["Site"]);
+        assert_covered(serde_fields::<Dev>(), &definitions["Dev"]);
+        assert_covered(serde_fields::<Triggers>(), &definitions["Triggers"]);
+        assert_covered(
+            serde_fields::<DurableObjects>(),
+            &definitions["DurableObjects"],
+        );
+        assert_covered(
+            serde_fields::<DurableObjectsClass>(),
+            &definitions["DurableObjectsClass"],
+        );
+        // `MigrationConfig` flattens its migration, so its own fields can't be
+        // read; check the flattened struct instead
+        assert_covered(
+            serde_fields::<DurableObjectsMigration>(),
+            &definitions["MigrationConfig"],
+        );
+        assert_covered(serde_fields::<RenameClass>(), &definitions["RenameClass"]);
+        assert_covered(
+            serde_fields::<TransferClass>(),
+            &definitions["TransferClass"],
+        );
+        assert_covered(
+            serde_fields::<ConfigKvNamespace>(),
+            &definitions["ConfigKvNamespace"],
+        );
+        assert_covered(
+            serde_fields::<ConfigR2Bucket>(),
+            &definitions["ConfigR2Bucket"],
+        );
+        assert_covered(
+            serde_fields::<ServiceBinding>(),
+            &definitions["ServiceBinding"],
+        );
+        assert_covered(serde_fields::<ZonedRoute>(), &definitions["ZonedRoute"]);
+        assert_covered(serde_fields::<WorkspaceConfig>(), &definitions["Workspace"]);
+    }
+
+    #[test]
+    fn it_only_references_existing_definitions() {
+        fn check_refs(value: &Value, definitions: &Map<String, Value>) {
+            match value {
+                Value::Object(object) => {
+                    if let Some(Value::String(reference)) = object.get("$ref") {
+                        let name = reference.trim_start_matches("#/definitions/");
+                        assert!(definitions.contains_key(name), "{} is undefined", reference);
+                    }
+                    object.values().for_each(|v| check_refs(v, definitions));
+                }
+                Value::Array(array) => array.iter().for_each(|v| check_refs(v, definitions)),
+                _ => {}
+            }
+        }
+
+        let schema = schema();
+        check_refs(&schema, schema["definitions"].as_object().unwrap());
+    }
+
+    #[test]
+    fn it_lists_every_module_type() {
+        let schema = schema();
+        let types = schema["definitions"]["ModuleRule"]["properties"]["type"]["enum"]
+            .as_array()
+            .unwrap();
+
+        assert_eq!(types.len(), ModuleType::iter().count());
+        assert!(types.contains(&json!("ESModule")));
+    }
+}
@@ -0,0 +1,34 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::binding::Binding;
+
+/// A binding that lets a worker call another worker directly, without going
+/// through a public URL.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ServiceBinding {
+    pub binding: String,
+    pub service: String,
+    pub environment: Option<String>,
+}
+
+impl fmt::Display for ServiceBinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "binding: {}, service: {}", self.binding, self.service)?;
+        if let Some(environment) = &self.environment {
+            write!(f, ", environment: {}", environment)?;
+        }
+        Ok(())
+    }
+}
+
+impl ServiceBinding {
+    pub fn binding(&self) -> Binding {
+        Binding::new_service(
+            self.binding.clone(),
+            self.service.clone(),
+            self.environment.clone(),
+        )
+    }
+}
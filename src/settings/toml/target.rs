@@ -2,11 +2,14 @@ use super::durable_objects::DurableObjects;
 use super::kv_namespace::KvNamespace;
 pub(crate) use super::manifest::LazyAccountId;
 use super::r2_bucket::R2Bucket;
+use super::service::ServiceBinding;
 use super::site::Site;
 use super::target_type::TargetType;
 use super::UsageModel;
 use super::{builder::Builder, migrations::Migrations};
 
+use serde_json::Value;
+
 use std::collections::HashMap;
 use std::env;
 
@@ -17,6 +20,7 @@ pub struct Target {
     pub account_id: LazyAccountId,
     pub kv_namespaces: Vec<KvNamespace>,
     pub r2_buckets: Vec<R2Bucket>,
+    pub services: Vec<ServiceBinding>,
     pub durable_objects: Option<DurableObjects>,
     pub migrations: Option<Migrations>,
     pub name: String,
@@ -24,7 +28,8 @@ pub struct Target {
     pub webpack_config: Option<String>,
     pub build: Option<Builder>,
     pub site: Option<Site>,
-    pub vars: Option<HashMap<String, String>>,
+    pub vars: Option<HashMap<String, Value>>,
+    pub secrets: Option<Vec<String>>,
     pub text_blobs: Option<HashMap<String, PathBuf>>,
     pub usage_model: Option<UsageModel>,
     pub wasm_modules: Option<HashMap<String, PathBuf>>,
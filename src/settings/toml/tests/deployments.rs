@@ -1,6 +1,8 @@
 use std::str::FromStr;
 
-use crate::deploy::{DeployTarget, ScheduleTarget, ZonedTarget, ZonelessTarget};
+use crate::deploy::{
+    CustomDomainTarget, DeployTarget, ScheduleTarget, Zone, ZonedTarget, ZonelessTarget,
+};
 use crate::settings::toml::route::Route;
 use crate::settings::toml::Manifest;
 
@@ -41,7 +43,7 @@ fn it_can_get_multiple_deployments() {
                 pattern: PATTERN.to_owned(),
                 id: None,
             }],
-            zone_id: ZONE_ID.to_owned(),
+            zone: Zone::Id(ZONE_ID.to_owned()),
         }),
         DeployTarget::Zoneless(ZonelessTarget {
             account_id: Some(ACCOUNT_ID.to_string()).into(),
@@ -114,7 +116,7 @@ fn it_can_get_a_single_route_zoned_get_deployments() {
         id: None,
     }];
     let expected_deployments = vec![DeployTarget::Zoned(ZonedTarget {
-        zone_id: ZONE_ID.to_string(),
+        zone: Zone::Id(ZONE_ID.to_string()),
         routes: expected_routes,
     })];
 
@@ -136,7 +138,7 @@ fn it_can_get_a_single_route_zoned_get_deployments_workers_dev_false() {
         id: None,
     }];
     let expected_deployments = vec![DeployTarget::Zoned(ZonedTarget {
-        zone_id: ZONE_ID.to_string(),
+        zone: Zone::Id(ZONE_ID.to_string()),
         routes: expected_routes,
     })];
     let environment = None;
@@ -318,7 +320,7 @@ fn it_can_get_a_multi_route_zoned_get_deployments() {
         })
         .collect();
     let expected_deployments = vec![DeployTarget::Zoned(ZonedTarget {
-        zone_id: ZONE_ID.to_string(),
+        zone: Zone::Id(ZONE_ID.to_string()),
         routes: expected_routes,
     })];
 
@@ -349,7 +351,7 @@ fn it_can_get_a_multi_route_zoned_get_deployments_workers_dev_false() {
         })
         .collect();
     let expected_deployments = vec![DeployTarget::Zoned(ZonedTarget {
-        zone_id: ZONE_ID.to_string(),
+        zone: Zone::Id(ZONE_ID.to_string()),
         routes: expected_routes,
     })];
 
@@ -381,7 +383,7 @@ fn it_can_get_multi_route_with_route() {
         })
         .collect();
     let expected_deployments = vec![DeployTarget::Zoned(ZonedTarget {
-        zone_id: ZONE_ID.to_string(),
+        zone: Zone::Id(ZONE_ID.to_string()),
         routes: expected_routes,
     })];
 
@@ -437,6 +439,108 @@ fn it_can_multi_route_get_deployments_empty_routes_list() {
     assert!(manifest.get_deployments(environment).is_ok());
 }
 
+#[test]
+fn it_splits_routes_with_their_own_zones() {
+    let script_name = "routes_across_zones";
+    let toml_string = format!(
+        r#"
+name = "{}"
+type = "javascript"
+zone_id = "{}"
+routes = [
+    "{}",
+    {{ pattern = "example.org/*", zone_name = "example.org" }},
+    {{ pattern = "api.example.org/*", zone_name = "example.org" }},
+    {{ pattern = "example.net/*", zone_id = "anotherzoneid" }},
+]
+"#,
+        script_name, ZONE_ID, PATTERN
+    );
+    let manifest = Manifest::from_str(&toml_string).unwrap();
+
+    let route = |pattern: &str| Route {
+        script: Some(script_name.to_string()),
+        pattern: pattern.to_string(),
+        id: None,
+    };
+    let expected_deployments = vec![
+        DeployTarget::Zoned(ZonedTarget {
+            zone: Zone::Id(ZONE_ID.to_string()),
+            routes: vec![route(PATTERN)],
+        }),
+        DeployTarget::Zoned(ZonedTarget {
+            zone: Zone::Name("example.org".to_string()),
+            routes: vec![route("example.org/*"), route("api.example.org/*")],
+        }),
+        DeployTarget::Zoned(ZonedTarget {
+            zone: Zone::Id("anotherzoneid".to_string()),
+            routes: vec![route("example.net/*")],
+        }),
+    ];
+
+    let actual_deployments = manifest.get_deployments(None).unwrap();
+
+    assert_eq!(actual_deployments, expected_deployments);
+}
+
+#[test]
+fn it_gets_custom_domain_deployments() {
+    let script_name = "custom_domains";
+    let toml_string = format!(
+        r#"
+name = "{}"
+type = "javascript"
+account_id = "{}"
+custom_domains = ["api.example.com", "example.com"]
+
+[env.staging]
+workers_dev = true
+"#,
+        script_name, ACCOUNT_ID
+    );
+    let manifest = Manifest::from_str(&toml_string).unwrap();
+
+    let expected_deployments = vec![DeployTarget::CustomDomain(CustomDomainTarget {
+        account_id: Some(ACCOUNT_ID.to_string()).into(),
+        script_name: script_name.to_string(),
+        hostnames: vec!["api.example.com".to_string(), "example.com".to_string()],
+    })];
+    assert_eq!(
+        manifest.get_deployments(None).unwrap(),
+        expected_deployments
+    );
+
+    // environments don't inherit custom domains
+    let staging_deployments = manifest.get_deployments(Some("staging")).unwrap();
+    assert!(matches!(&*staging_deployments, [DeployTarget::Zoneless(_)]));
+}
+
+#[test]
+fn it_needs_a_zone_for_every_route() {
+    let toml_string = r#"
+name = "routes_missing_zone"
+type = "javascript"
+routes = ["example.com/*", { pattern = "example.org/*", zone_name = "example.org" }]
+"#;
+    let manifest = Manifest::from_str(toml_string).unwrap();
+
+    let error = manifest.get_deployments(None).unwrap_err().to_string();
+
+    assert!(error.contains("example.com/*"), "{}", error);
+}
+
+#[test]
+fn it_errors_on_routes_with_two_zones() {
+    let toml_string = r#"
+name = "route_with_two_zones"
+type = "javascript"
+routes = [{ pattern = "example.org/*", zone_name = "example.org", zone_id = "samplezoneid" }]
+"#;
+    let manifest = Manifest::from_str(toml_string).unwrap();
+
+    assert!(manifest.get_deployments(None).is_err());
+}
+
 #[test]
 fn it_can_multi_route_get_deployments_empty_route() {
     let script_name = "multi_route_empty_route";
@@ -471,7 +575,7 @@ fn it_can_get_a_route_with_routes() {
         .collect();
     let expected_deployments = vec![DeployTarget::Zoned(ZonedTarget {
         routes: expected_routes,
-        zone_id: ZONE_ID.to_owned(),
+        zone: Zone::Id(ZONE_ID.to_owned()),
     })];
 
     let environment = None;
@@ -494,7 +598,10 @@ fn it_gets_deployments_with_route_and_workers_dev_true() {
 
     let deployments = manifest.get_deployments(environment).unwrap();
     match dbg!(&*deployments) {
-        [DeployTarget::Zoned(ZonedTarget { zone_id, routes }), DeployTarget::Zoneless(ZonelessTarget {
+        [DeployTarget::Zoned(ZonedTarget {
+            zone: Zone::Id(zone_id),
+            routes,
+        }), DeployTarget::Zoneless(ZonelessTarget {
             account_id: _,
             script_name: actual_script_name,
         })] => {
@@ -525,7 +632,10 @@ fn it_gets_deployments_with_routes_and_workers_dev_true() {
 
     let deployments = manifest.get_deployments(environment).unwrap();
     match dbg!(&*deployments) {
-        [DeployTarget::Zoned(ZonedTarget { zone_id, routes }), DeployTarget::Zoneless(ZonelessTarget {
+        [DeployTarget::Zoned(ZonedTarget {
+            zone: Zone::Id(zone_id),
+            routes,
+        }), DeployTarget::Zoneless(ZonelessTarget {
             account_id: _,
             script_name: actual_script_name,
         })] => {
@@ -676,7 +786,7 @@ fn when_top_level_empty_env_zoned_single_route() {
         id: None,
     }];
     let expected_deployments = vec![DeployTarget::Zoned(ZonedTarget {
-        zone_id: ZONE_ID.to_string(),
+        zone: Zone::Id(ZONE_ID.to_string()),
         routes: expected_routes,
     })];
 
@@ -757,7 +867,7 @@ fn when_top_level_empty_zoned_multi_route_env() {
         .collect();
 
     let expected_deployments = vec![DeployTarget::Zoned(ZonedTarget {
-        zone_id: ZONE_ID.to_string(),
+        zone: Zone::Id(ZONE_ID.to_string()),
         routes: expected_routes,
     })];
 
@@ -894,7 +1004,7 @@ fn when_top_level_zoneless_env_zoned_single_route() {
     }];
     let expected_deployments = vec![
         DeployTarget::Zoned(ZonedTarget {
-            zone_id: ZONE_ID.to_string(),
+            zone: Zone::Id(ZONE_ID.to_string()),
             routes: expected_routes,
         }),
         DeployTarget::Zoneless(ZonelessTarget {
@@ -970,7 +1080,7 @@ fn when_top_level_zoneless_env_zoned_multi_route_route_key_present() {
     let expected_deployments = vec![
         DeployTarget::Zoned(ZonedTarget {
             routes: expected_routes,
-            zone_id: ZONE_ID.to_owned(),
+            zone: Zone::Id(ZONE_ID.to_owned()),
         }),
         DeployTarget::Zoneless(ZonelessTarget {
             account_id: Some(ACCOUNT_ID.to_string()).into(),
@@ -1033,7 +1143,7 @@ fn when_top_level_zoneless_env_zoned_multi_route() {
 
     let expected_deployments = vec![
         DeployTarget::Zoned(ZonedTarget {
-            zone_id: ZONE_ID.to_string(),
+            zone: Zone::Id(ZONE_ID.to_string()),
             routes: expected_routes,
         }),
         DeployTarget::Zoneless(ZonelessTarget {
@@ -1158,7 +1268,7 @@ fn when_top_level_zoned_env_zoned_single_route_zone_id_missing() {
         id: None,
     }];
     let expected_deployments = vec![DeployTarget::Zoned(ZonedTarget {
-        zone_id: ZONE_ID.to_string(),
+        zone: Zone::Id(ZONE_ID.to_string()),
         routes: expected_routes,
     })];
 
@@ -1192,7 +1302,7 @@ fn when_top_level_zoned_env_zoned_single_route() {
         id: None,
     }];
     let expected_deployments = vec![DeployTarget::Zoned(ZonedTarget {
-        zone_id: env_zone_id.to_string(),
+        zone: Zone::Id(env_zone_id.to_string()),
         routes: expected_routes,
     })];
 
@@ -1,4 +1,5 @@
 mod deployments;
+mod overrides;
 
 use super::*;
 
@@ -123,6 +124,148 @@ fn it_builds_from_environments_config_with_r2() {
     }
 }
 
+#[test]
+fn it_does_not_inherit_bindings_without_opting_in() {
+    let toml_path = toml_fixture_path("inherit");
+    let manifest = Manifest::new(&toml_path).unwrap();
+
+    let target = manifest.get_target(Some("isolated"), false).unwrap();
+    assert!(target.kv_namespaces.is_empty());
+    assert!(target.durable_objects.is_none());
+
+    let vars = target.vars.unwrap();
+    assert_eq!(vars.len(), 1);
+    assert_eq!(vars["LOG_LEVEL"], "debug");
+}
+
+#[test]
+fn it_inherits_bindings_listed_in_inherit() {
+    let toml_path = toml_fixture_path("inherit");
+    let manifest = Manifest::new(&toml_path).unwrap();
+
+    let target = manifest.get_target(Some("staging"), false).unwrap();
+
+    let cache = KvNamespace {
+        id: "topcacheid".to_string(),
+        binding: "CACHE".to_string(),
+    };
+    let sessions = KvNamespace {
+        id: "stagingsessionsid".to_string(),
+        binding: "SESSIONS".to_string(),
+    };
+    assert_eq!(target.kv_namespaces.len(), 2);
+    assert!(target.kv_namespaces.contains(&cache));
+    assert!(target.kv_namespaces.contains(&sessions));
+
+    let classes = target.durable_objects.unwrap().classes.unwrap();
+    assert_eq!(classes.len(), 1);
+    assert_eq!(classes[0].binding, "COUNTER");
+    assert_eq!(classes[0].class_name, "Counter");
+
+    let vars = target.vars.unwrap();
+    assert_eq!(vars.len(), 2);
+    assert_eq!(vars["API_HOST"], "api.example.com");
+    assert_eq!(vars["LOG_LEVEL"], "debug");
+}
+
+#[test]
+fn it_builds_service_bindings_from_environments() {
+    let toml_path = toml_fixture_path("services");
+    let manifest = Manifest::new(&toml_path).unwrap();
+
+    let target = manifest.get_target(None, false).unwrap();
+    assert_eq!(target.services.len(), 2);
+
+    let target = manifest.get_target(Some("isolated"), false).unwrap();
+    assert!(target.services.is_empty());
+
+    let target = manifest.get_target(Some("staging"), false).unwrap();
+    let auth = ServiceBinding {
+        binding: "AUTH".to_string(),
+        service: "auth".to_string(),
+        environment: None,
+    };
+    let billing = ServiceBinding {
+        binding: "BILLING".to_string(),
+        service: "billing".to_string(),
+        environment: Some("staging".to_string()),
+    };
+    assert_eq!(target.services, vec![auth, billing]);
+}
+
+#[test]
+fn it_builds_typed_vars() {
+    let toml_path = toml_fixture_path("vars");
+    let manifest = Manifest::new(&toml_path).unwrap();
+
+    let vars = manifest.get_target(None, false).unwrap().vars.unwrap();
+    assert_eq!(vars["API_HOST"], serde_json::json!("api.example.com"));
+    assert_eq!(vars["MAX_RETRIES"], serde_json::json!(3));
+    assert_eq!(vars["SAMPLE_RATE"], serde_json::json!(0.5));
+    assert_eq!(vars["BETA"], serde_json::json!(true));
+    assert_eq!(
+        vars["FEATURES"],
+        serde_json::json!({ "search": true, "regions": ["us", "eu"] })
+    );
+}
+
+#[test]
+fn it_rejects_unknown_inherit_keys() {
+    let toml = r#"
+        name = "worker"
+        type = "javascript"
+        workers_dev = true
+
+        [env.staging]
+        inherit = ["routes"]
+    "#;
+
+    assert!(Manifest::from_str(toml).is_err());
+}
+
+#[test]
+fn it_interpolates_environment_variables() {
+    env::set_var("WRANGLER_TEST_INTERPOLATED_ACCOUNT_ID", "fakeaccountid");
+    env::set_var("WRANGLER_TEST_INTERPOLATED_ZONE_ID", "samplezoneid");
+    env::set_var("WRANGLER_TEST_INTERPOLATED_HOST", "example.com");
+    let toml_path = toml_fixture_path("interpolation");
+
+    let manifest = Manifest::new(&toml_path).unwrap();
+
+    assert_eq!(
+        manifest.get_account_id(None).unwrap(),
+        "fakeaccountid".to_string()
+    );
+    let target = manifest.get_target(None, false).unwrap();
+    assert_eq!(target.vars.unwrap()["API_HOST"], "api.example.com");
+
+    let environment = manifest.get_environment(Some("production")).unwrap();
+    let environment = environment.unwrap();
+    assert_eq!(environment.zone_id, Some("samplezoneid".to_string()));
+    assert_eq!(environment.routes, Some(vec!["example.com/*".into()]));
+}
+
+#[test]
+fn it_errors_on_undefined_environment_variables() {
+    let toml_path = toml_fixture_path("interpolation");
+    env::remove_var("WRANGLER_TEST_INTERPOLATED_MISSING");
+    let config = fs::read_to_string(&toml_path).unwrap().replace(
+        "WRANGLER_TEST_INTERPOLATED_HOST",
+        "WRANGLER_TEST_INTERPOLATED_MISSING",
+    );
+    let tmp = tempfile::tempdir().unwrap();
+    let config_path = tmp.path().join("wrangler.toml");
+    fs::write(&config_path, config).unwrap();
+
+    let error = Manifest::new(&config_path).unwrap_err().to_string();
+
+    assert!(
+        error.contains("WRANGLER_TEST_INTERPOLATED_MISSING"),
+        "{}",
+        error
+    );
+}
+
 #[test]
 fn parses_same_from_config_path_as_string() {
     env::remove_var("CF_ACCOUNT_ID");
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::settings::toml::migrations::{MigrationConfig, Migrations};
+use crate::settings::toml::{Manifest, UsageModel};
+
+use super::fixtures::{self, EnvConfig, WranglerToml, TEST_ENV_NAME};
+
+fn top_level() -> WranglerToml {
+    WranglerToml {
+        usage_model: Some("bundled"),
+        compatibility_date: Some("2021-11-10"),
+        compatibility_flags: Some(vec!["formdata_parser_supports_files"]),
+        wasm_modules: Some(vec![("MODULE", "./top_level.wasm")].into_iter().collect()),
+        migrations: Some(vec![fixtures::MigrationConfig {
+            tag: "v1",
+            new_classes: Some(vec!["Counter"]),
+        }]),
+        ..WranglerToml::javascript("overrides")
+    }
+}
+
+fn manifest_with_env(env_config: EnvConfig) -> Manifest {
+    let mut test_toml = top_level();
+    test_toml
+        .env
+        .get_or_insert_with(Default::default)
+        .insert(TEST_ENV_NAME, env_config);
+
+    let toml_string = toml::to_string(&test_toml).unwrap();
+    Manifest::from_str(&toml_string).unwrap()
+}
+
+fn migration_tags(migrations: Option<Migrations>) -> Vec<String> {
+    match migrations {
+        Some(Migrations::List { migrations, .. }) => migrations
+            .into_iter()
+            .map(|MigrationConfig { tag, .. }| tag)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[test]
+fn it_inherits_top_level_values_when_env_omits_them() {
+    let manifest = manifest_with_env(EnvConfig::default());
+
+    let target = manifest.get_target(Some(TEST_ENV_NAME), false).unwrap();
+
+    assert_eq!(target.usage_model, Some(UsageModel::Bundled));
+    assert_eq!(target.compatibility_date, Some("2021-11-10".to_string()));
+    assert_eq!(
+        target.compatibility_flags,
+        vec!["formdata_parser_supports_files".to_string()]
+    );
+    assert_eq!(
+        target.wasm_modules.unwrap()["MODULE"],
+        PathBuf::from("./top_level.wasm")
+    );
+    assert_eq!(migration_tags(target.migrations), vec!["v1".to_string()]);
+}
+
+#[test]
+fn it_uses_env_values_when_present() {
+    let env_config = EnvConfig {
+        usage_model: Some("unbound"),
+        compatibility_date: Some("2022-03-21"),
+        compatibility_flags: Some(vec![]),
+        wasm_modules: Some(vec![("MODULE", "./staging.wasm")].into_iter().collect()),
+        migrations: Some(vec![
+            fixtures::MigrationConfig {
+                tag: "v1",
+                new_classes: Some(vec!["Counter"]),
+            },
+            fixtures::MigrationConfig {
+                tag: "v2",
+                new_classes: Some(vec!["RateLimiter"]),
+            },
+        ]),
+        ..EnvConfig::default()
+    };
+    let manifest = manifest_with_env(env_config);
+
+    let target = manifest.get_target(Some(TEST_ENV_NAME), false).unwrap();
+
+    assert_eq!(target.usage_model, Some(UsageModel::Unbound));
+    assert_eq!(target.compatibility_date, Some("2022-03-21".to_string()));
+    assert!(target.compatibility_flags.is_empty());
+
+    let wasm_modules: HashMap<String, PathBuf> = target.wasm_modules.unwrap();
+    assert_eq!(wasm_modules.len(), 1);
+    assert_eq!(wasm_modules["MODULE"], PathBuf::from("./staging.wasm"));
+
+    assert_eq!(
+        migration_tags(target.migrations),
+        vec!["v1".to_string(), "v2".to_string()]
+    );
+
+    // the top level target is unaffected by the environment
+    let target = manifest.get_target(None, false).unwrap();
+    assert_eq!(target.usage_model, Some(UsageModel::Bundled));
+    assert_eq!(target.compatibility_date, Some("2021-11-10".to_string()));
+}
+
+#[test]
+fn it_skips_env_migrations_for_preview() {
+    let env_config = EnvConfig {
+        migrations: Some(vec![fixtures::MigrationConfig {
+            tag: "v2",
+            new_classes: Some(vec!["RateLimiter"]),
+        }]),
+        ..EnvConfig::default()
+    };
+    let manifest = manifest_with_env(env_config);
+
+    let target = manifest.get_target(Some(TEST_ENV_NAME), true).unwrap();
+
+    assert!(target.migrations.is_none());
+}
+
+#[test]
+fn it_replaces_top_level_secrets_with_env_secrets() {
+    let mut test_toml = top_level();
+    test_toml.secrets = Some(vec!["STRIPE_KEY"]);
+    let toml_string = toml::to_string(&test_toml).unwrap();
+    let manifest = Manifest::from_str(&toml_string).unwrap();
+    let target = manifest.get_target(None, false).unwrap();
+    assert_eq!(target.secrets, Some(vec!["STRIPE_KEY".to_string()]));
+
+    let manifest = manifest_with_env(EnvConfig {
+        secrets: Some(vec!["SENTRY_DSN"]),
+        ..Default::default()
+    });
+    let target = manifest.get_target(Some(TEST_ENV_NAME), false).unwrap();
+    assert_eq!(target.secrets, Some(vec!["SENTRY_DSN".to_string()]));
+}
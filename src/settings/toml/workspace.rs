@@ -0,0 +1,325 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::config_format::ConfigFormat;
+use super::extends;
+use super::interpolate;
+use super::manifest::{config_from_value, Manifest};
+
+/// Commands that run on workspace members, picked with `--worker` or `--all`.
+pub const MEMBER_COMMANDS: [&str; 6] = [
+    "build",
+    "publish",
+    "tail",
+    "secret",
+    "deployments",
+    "rollback",
+];
+
+/// [`MEMBER_COMMANDS`], listed the way messages to users name them.
+pub fn member_commands() -> String {
+    match MEMBER_COMMANDS.split_last() {
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+        None => String::new(),
+    }
+}
+
+/// Top level keys of a workspace root that members don't inherit.
+const NOT_INHERITED: [&str; 3] = ["name", "env", "workspace"];
+
+/// The `[workspace]` table of a root configuration file.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkspaceConfig {
+    /// Directories containing the configuration file of each member worker,
+    /// relative to the root configuration file
+    pub members: Vec<PathBuf>,
+}
+
+/// A root configuration file listing several workers, each with its own
+/// configuration file in its own directory. Members inherit every top level
+/// key of the root except `name` and `env`, merging tables key by key.
+#[derive(Clone, Debug)]
+pub struct Workspace {
+    root_dir: PathBuf,
+    config: WorkspaceConfig,
+    shared: Map<String, Value>,
+}
+
+/// A member worker of a workspace.
+#[derive(Clone, Debug)]
+pub struct Member {
+    /// The member's directory; its paths are relative to it
+    pub dir: PathBuf,
+    pub manifest: Manifest,
+}
+
+impl Workspace {
+    /// The workspace declared by the configuration file at `config_path`, if it
+    /// has a `[workspace]` table.
+    pub fn load(config_path: &Path) -> Result<Option<Workspace>> {
+        if !config_path.exists() {
+            return Ok(None);
+        }
+
+        let mut root = match read_value(config_path)? {
+            Value::Object(root) => root,
+            _ => return Ok(None),
+        };
+        let config = match root.remove("workspace") {
+            Some(workspace) => serde_json::from_value(workspace)
+                .map_err(|e| anyhow!("Invalid [workspace] in {}: {}", config_path.display(), e))?,
+            None => return Ok(None),
+        };
+        for key in &NOT_INHERITED {
+            root.remove(*key);
+        }
+
+        let root_dir = match config_path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        Ok(Some(Workspace {
+            root_dir,
+            config,
+            shared: root,
+        }))
+    }
+
+    /// The workspace whose members include the configuration file at
+    /// `config_path`, found by looking in the directories above it.
+    pub fn containing(config_path: &Path) -> Result<Option<Workspace>> {
+        let member_dir = match fs::canonicalize(config_path)?.parent() {
+            Some(dir) => dir.to_path_buf(),
+            None => return Ok(None),
+        };
+        for dir in member_dir.ancestors().skip(1) {
+            let root_path = ConfigFormat::locate(&dir.join(ConfigFormat::Toml.file_name()));
+            // configuration files above that don't parse aren't workspaces of ours
+            let workspace = match Workspace::load(&root_path) {
+                Ok(Some(workspace)) => workspace,
+                _ => continue,
+            };
+            let is_member = workspace.config.members.iter().any(|member| {
+                fs::canonicalize(workspace.root_dir.join(member))
+                    .ok()
+                    .as_ref()
+                    == Some(&member_dir)
+            });
+            if is_member {
+                return Ok(Some(workspace));
+            }
+        }
+        Ok(None)
+    }
+
+    /// A member's own configuration, over the keys it inherits from the root.
+    pub(super) fn inherit(&self, own: Value, config_path: &Path) -> Result<Value> {
+        anyhow::ensure!(own.is_object(), "{} is not a table", config_path.display());
+        let mut merged = Value::Object(self.shared.clone());
+        extends::deep_merge(&mut merged, own);
+        Ok(merged)
+    }
+
+    /// Every member, in the order they are listed.
+    pub fn members(&self) -> Result<Vec<Member>> {
+        self.config
+            .members
+            .iter()
+            .map(|dir| self.member(&self.root_dir.join(dir)))
+            .collect()
+    }
+
+    /// The members a command should run on: the one named by `worker`, or all
+    /// of them if `all` is set.
+    pub fn select(&self, worker: Option<&str>, all: bool) -> Result<Vec<Member>> {
+        let members = self.members()?;
+        let names = || {
+            members
+                .iter()
+                .map(|m| m.manifest.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        match (worker, all) {
+            (_, true) => Ok(members),
+            (Some(worker), false) => {
+                let selected: Vec<Member> = members
+                    .iter()
+                    .filter(|m| m.manifest.name == worker || m.dir.ends_with(worker))
+                    .cloned()
+                    .collect();
+                if selected.is_empty() {
+                    anyhow::bail!(
+                        "There is no worker named \"{}\" in this workspace; its members are {}",
+                        worker,
+                        names()
+                    )
+                }
+                Ok(selected)
+            }
+            (None, false) => anyhow::bail!(
+                "This configuration file is a workspace; pick a member with --worker <name>, or use --all. Its members are {}",
+                names()
+            ),
+        }
+    }
+
+    fn member(&self, dir: &Path) -> Result<Member> {
+        let config_path = ConfigFormat::locate(&dir.join(ConfigFormat::Toml.file_name()));
+        anyhow::ensure!(
+            config_path.exists(),
+            "Workspace member {} has no configuration file",
+            dir.display()
+        );
+
+        let merged = self.inherit(read_value(&config_path)?, &config_path)?;
+
+        let config = config_from_value(&merged)?;
+        let manifest = Manifest::from_config(config)
+            .map_err(|e| anyhow!("{}: {}", config_path.display(), e))?;
+
+        Ok(Member {
+            dir: dir.to_path_buf(),
+            manifest,
+        })
+    }
+}
+
+fn read_value(config_path: &Path) -> Result<Value> {
+    let mut value = extends::load(config_path)?.value;
+    if interpolate::enabled() {
+        interpolate::interpolate(&mut value)?;
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn workspace() -> (tempfile::TempDir, Workspace) {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            &dir.path().join("wrangler.toml"),
+            r#"
+name = "monorepo"
+type = "javascript"
+account_id = "sharedaccountid"
+workers_dev = true
+compatibility_date = "2022-01-01"
+
+[workspace]
+members = ["workers/auth", "workers/api"]
+
+[env.production]
+zone_id = "notinherited"
+"#,
+        );
+        write(
+            &dir.path().join("workers/auth/wrangler.toml"),
+            "name = \"auth\"\n",
+        );
+        write(
+            &dir.path().join("workers/api/wrangler.toml"),
+            "name = \"api\"\ncompatibility_date = \"2022-02-02\"\n",
+        );
+
+        let workspace = Workspace::load(&dir.path().join("wrangler.toml"))
+            .unwrap()
+            .unwrap();
+        (dir, workspace)
+    }
+
+    #[test]
+    fn it_inherits_top_level_keys() {
+        let (_dir, workspace) = workspace();
+
+        let members = workspace.members().unwrap();
+        assert_eq!(members.len(), 2);
+
+        let auth = &members[0].manifest;
+        assert_eq!(auth.name, "auth");
+        assert_eq!(auth.account_id.load().unwrap(), "sharedaccountid");
+        assert_eq!(auth.compatibility_date.as_deref(), Some("2022-01-01"));
+        assert!(auth.env.is_none());
+
+        let api = &members[1].manifest;
+        assert_eq!(api.compatibility_date.as_deref(), Some("2022-02-02"));
+        assert!(members[1].dir.ends_with("workers/api"));
+    }
+
+    #[test]
+    fn it_merges_tables_key_by_key() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            &dir.path().join("wrangler.toml"),
+            r#"
+name = "monorepo"
+type = "javascript"
+
+[build]
+command = "npm run build"
+[build.upload]
+format = "modules"
+main = "./index.mjs"
+
+[workspace]
+members = ["workers/api"]
+"#,
+        );
+        write(
+            &dir.path().join("workers/api/wrangler.toml"),
+            "name = \"api\"\n\n[build.upload]\nformat = \"modules\"\nmain = \"./api.mjs\"\n",
+        );
+
+        let workspace = Workspace::load(&dir.path().join("wrangler.toml"))
+            .unwrap()
+            .unwrap();
+        let members = workspace.members().unwrap();
+        let build = members[0].manifest.build.as_ref().unwrap();
+
+        assert_eq!(build.command.as_deref(), Some("npm run build"));
+        match &build.upload {
+            crate::settings::toml::UploadFormat::Modules { main, .. } => {
+                assert_eq!(main, "./api.mjs")
+            }
+            _ => panic!("expected a modules upload"),
+        }
+    }
+
+    #[test]
+    fn it_selects_members() {
+        let (_dir, workspace) = workspace();
+
+        let selected = workspace.select(Some("api"), false).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].manifest.name, "api");
+
+        assert_eq!(workspace.select(None, true).unwrap().len(), 2);
+        assert!(workspace.select(None, false).is_err());
+        assert!(workspace.select(Some("billing"), false).is_err());
+    }
+
+    #[test]
+    fn it_ignores_ordinary_configs() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("wrangler.toml");
+        write(&config_path, "name = \"worker\"\ntype = \"javascript\"\n");
+
+        assert!(Workspace::load(&config_path).unwrap().is_none());
+    }
+}
@@ -334,6 +334,7 @@ mod tests {
             account_id: None.into(),
             kv_namespaces: Vec::new(),
             r2_buckets: Vec::new(),
+            services: Vec::new(),
             durable_objects: None,
             migrations: None,
             name: "".to_string(),
@@ -342,6 +343,7 @@ mod tests {
             site: Some(site),
             build: None,
             vars: None,
+            secrets: None,
             text_blobs: None,
             usage_model: None,
             wasm_modules: None,
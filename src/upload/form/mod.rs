@@ -1,4 +1,5 @@
 mod modules_worker;
+mod part;
 mod plain_text;
 mod project_assets;
 mod service_worker;
@@ -16,6 +17,7 @@ use crate::settings::toml::{Target, TargetType, UploadFormat, UsageModel};
 use crate::sites::AssetManifest;
 use crate::wranglerjs;
 
+pub use part::{into_form, write_parts, FormPart, PartContent};
 use plain_text::PlainText;
 pub use project_assets::{ModuleConfig, ModuleType};
 use project_assets::{ModulesAssets, ServiceWorkerAssets};
@@ -30,11 +32,21 @@ pub fn build(
     asset_manifest: Option<AssetManifest>,
     session_config: Option<serde_json::Value>,
 ) -> Result<Form> {
+    part::into_form(build_parts(target, asset_manifest, session_config)?)
+}
+
+/// The parts of the upload form for a target, in the order they are sent.
+pub fn build_parts(
+    target: &Target,
+    asset_manifest: Option<AssetManifest>,
+    session_config: Option<serde_json::Value>,
+) -> Result<Vec<FormPart>> {
     let target_type = &target.target_type;
     let compatibility_date = target.compatibility_date.clone();
     let compatibility_flags = target.compatibility_flags.clone();
     let kv_namespaces = &target.kv_namespaces;
     let r2_buckets = &target.r2_buckets;
+    let services = &target.services;
     let durable_object_classes = target
         .durable_objects
         .as_ref()
@@ -93,13 +105,14 @@ pub fn build(
                 wasm_modules,
                 kv_namespaces: kv_namespaces.to_vec(),
                 r2_buckets: r2_buckets.to_vec(),
+                services: services.to_vec(),
                 durable_object_classes,
                 text_blobs,
                 plain_texts,
                 usage_model,
             };
 
-            service_worker::build_form(&assets, session_config)
+            service_worker::build_parts(&assets, session_config)
         }
         TargetType::JavaScript => match &target.build {
             Some(config) => match &config.upload {
@@ -116,13 +129,14 @@ pub fn build(
                         wasm_modules,
                         kv_namespaces: kv_namespaces.to_vec(),
                         r2_buckets: r2_buckets.to_vec(),
+                        services: services.to_vec(),
                         durable_object_classes,
                         text_blobs,
                         plain_texts,
                         usage_model,
                     };
 
-                    service_worker::build_form(&assets, session_config)
+                    service_worker::build_parts(&assets, session_config)
                 }
                 UploadFormat::Modules { main, dir, rules } => {
                     let migration = match &target.migrations {
@@ -137,6 +151,7 @@ pub fn build(
                         module_config.get_modules()?,
                         kv_namespaces.to_vec(),
                         r2_buckets.to_vec(),
+                        services.to_vec(),
                         durable_object_classes,
                         migration,
                         text_blobs,
@@ -144,7 +159,7 @@ pub fn build(
                         usage_model,
                     )?;
 
-                    modules_worker::build_form(&assets, session_config)
+                    modules_worker::build_parts(&assets, session_config)
                 }
             },
             None => {
@@ -160,13 +175,14 @@ pub fn build(
                     wasm_modules,
                     kv_namespaces: kv_namespaces.to_vec(),
                     r2_buckets: r2_buckets.to_vec(),
+                    services: services.to_vec(),
                     durable_object_classes,
                     text_blobs,
                     plain_texts,
                     usage_model,
                 };
 
-                service_worker::build_form(&assets, session_config)
+                service_worker::build_parts(&assets, session_config)
             }
         },
         TargetType::Webpack => {
@@ -191,13 +207,14 @@ pub fn build(
                 wasm_modules,
                 kv_namespaces: kv_namespaces.to_vec(),
                 r2_buckets: r2_buckets.to_vec(),
+                services: services.to_vec(),
                 durable_object_classes,
                 text_blobs,
                 plain_texts,
                 usage_model,
             };
 
-            service_worker::build_form(&assets, session_config)
+            service_worker::build_parts(&assets, session_config)
         }
     }
 }
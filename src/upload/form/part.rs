@@ -0,0 +1,162 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::Result;
+use reqwest::blocking::multipart::{Form, Part};
+
+/// A part of the upload form. Forms are built as a list of these first, so that
+/// they can be inspected or written to disk as well as sent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormPart {
+    pub name: String,
+    /// Defaults to the file name of `PartContent::File`
+    pub file_name: Option<String>,
+    /// Defaults to a guess from the file extension of `PartContent::File`
+    pub content_type: Option<String>,
+    pub content: PartContent,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PartContent {
+    Text(String),
+    File(PathBuf),
+}
+
+impl FormPart {
+    pub fn text(name: &str, file_name: &str, content_type: &str, text: String) -> FormPart {
+        FormPart {
+            name: name.to_string(),
+            file_name: Some(file_name.to_string()),
+            content_type: Some(content_type.to_string()),
+            content: PartContent::Text(text),
+        }
+    }
+
+    pub fn file(name: &str, path: PathBuf) -> FormPart {
+        FormPart {
+            name: name.to_string(),
+            file_name: None,
+            content_type: None,
+            content: PartContent::File(path),
+        }
+    }
+
+    pub fn size(&self) -> Result<u64> {
+        match &self.content {
+            PartContent::Text(text) => Ok(text.len() as u64),
+            PartContent::File(path) => Ok(fs::metadata(path)?.len()),
+        }
+    }
+
+    /// Where the part goes when the form is written to a directory: its file
+    /// name if it has one, or else its name.
+    pub fn output_path(&self) -> Result<PathBuf> {
+        let path = PathBuf::from(match &self.file_name {
+            Some(file_name) if !file_name.is_empty() => file_name,
+            _ => &self.name,
+        });
+
+        // part names come from module paths and bindings, so keep them inside
+        // the output directory
+        anyhow::ensure!(
+            path.components().all(|c| matches!(c, Component::Normal(_))),
+            "Can't write the form part \"{}\" to {}",
+            self.name,
+            path.display()
+        );
+        Ok(path)
+    }
+
+    fn into_part(self) -> Result<Part> {
+        let mut part = match self.content {
+            PartContent::Text(text) => Part::text(text),
+            PartContent::File(path) => Part::file(path)?,
+        };
+        if let Some(file_name) = self.file_name {
+            part = part.file_name(file_name);
+        }
+        if let Some(content_type) = self.content_type {
+            part = part.mime_str(&content_type)?;
+        }
+
+        Ok(part)
+    }
+}
+
+pub fn into_form(parts: Vec<FormPart>) -> Result<Form> {
+    let mut form = Form::new();
+    for part in parts {
+        let name = part.name.clone();
+        form = form.part(name, part.into_part()?);
+    }
+
+    log::info!("building form");
+    log::info!("{:#?}", &form);
+
+    Ok(form)
+}
+
+/// Writes every part of a form into `dir`, returning the paths written.
+pub fn write_parts(parts: &[FormPart], dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut written = Vec::new();
+    for part in parts {
+        let path = dir.join(part.output_path()?);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        match &part.content {
+            PartContent::Text(text) => fs::write(&path, text)?,
+            PartContent::File(source) => {
+                fs::copy(source, &path)?;
+            }
+        }
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_writes_parts_by_file_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("worker.js");
+        fs::write(&script, "addEventListener()").unwrap();
+        let out = dir.path().join("out");
+
+        let parts = vec![
+            FormPart::text("metadata", "metadata.json", "application/json", "{}".into()),
+            FormPart::file("worker.js", script),
+            FormPart::text(
+                "lib/util.mjs",
+                "lib/util.mjs",
+                "application/javascript+module",
+                "export {}".into(),
+            ),
+        ];
+        let written = write_parts(&parts, &out).unwrap();
+
+        assert_eq!(
+            written,
+            vec![
+                out.join("metadata.json"),
+                out.join("worker.js"),
+                out.join("lib/util.mjs")
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(out.join("worker.js")).unwrap(),
+            "addEventListener()"
+        );
+    }
+
+    #[test]
+    fn it_keeps_parts_inside_the_output_directory() {
+        let part = FormPart::text("../escape", "../escape", "text/plain", String::new());
+
+        assert!(part.output_path().is_err());
+    }
+}
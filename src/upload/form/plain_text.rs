@@ -1,19 +1,57 @@
 use super::binding::Binding;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct PlainText {
     pub name: String,
-    pub value: String,
+    pub value: Value,
 }
 
 impl PlainText {
-    pub fn new(name: String, value: String) -> Result<Self> {
+    pub fn new(name: String, value: Value) -> Result<Self> {
         Ok(Self { name, value })
     }
 
+    // strings stay plain_text bindings so existing workers see no difference;
+    // anything else is bound as JSON and arrives already parsed
     pub fn binding(&self) -> Binding {
-        Binding::new_plain_text(self.name.clone(), self.value.clone())
+        match &self.value {
+            Value::String(text) => Binding::new_plain_text(self.name.clone(), text.clone()),
+            json => Binding::new_json(self.name.clone(), json.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    fn binding(value: Value) -> Value {
+        let plain_text = PlainText::new("VAR".to_string(), value).unwrap();
+        serde_json::to_value(plain_text.binding()).unwrap()
+    }
+
+    #[test]
+    fn it_binds_strings_as_plain_text() {
+        assert_eq!(
+            binding(json!("42")),
+            json!({ "type": "plain_text", "name": "VAR", "text": "42" })
+        );
+    }
+
+    #[test]
+    fn it_binds_other_values_as_json() {
+        assert_eq!(
+            binding(json!(42)),
+            json!({ "type": "json", "name": "VAR", "json": 42 })
+        );
+        assert_eq!(
+            binding(json!({ "beta": true, "limits": [1, 2] })),
+            json!({ "type": "json", "name": "VAR", "json": { "beta": true, "limits": [1, 2] } })
+        );
     }
 }
@@ -15,6 +15,7 @@ use super::UsageModel;
 
 use crate::settings::toml::{
     migrations::ApiMigration, DurableObjectsClass, KvNamespace, ModuleRule, R2Bucket,
+    ServiceBinding,
 };
 use std::collections::{HashMap, HashSet};
 
@@ -26,6 +27,7 @@ pub struct ServiceWorkerAssets {
     pub wasm_modules: Vec<WasmModule>,
     pub kv_namespaces: Vec<KvNamespace>,
     pub r2_buckets: Vec<R2Bucket>,
+    pub services: Vec<ServiceBinding>,
     pub durable_object_classes: Vec<DurableObjectsClass>,
     pub text_blobs: Vec<TextBlob>,
     pub plain_texts: Vec<PlainText>,
@@ -48,6 +50,10 @@ impl ServiceWorkerAssets {
             let binding = r2.binding();
             bindings.push(binding);
         }
+        for service in &self.services {
+            let binding = service.binding();
+            bindings.push(binding);
+        }
         for do_ns in &self.durable_object_classes {
             let binding = do_ns.binding();
             bindings.push(binding);
@@ -323,6 +329,7 @@ pub struct ModulesAssets {
     pub manifest: ModuleManifest,
     pub kv_namespaces: Vec<KvNamespace>,
     pub r2_buckets: Vec<R2Bucket>,
+    pub services: Vec<ServiceBinding>,
     pub durable_object_classes: Vec<DurableObjectsClass>,
     pub migration: Option<ApiMigration>,
     pub text_blobs: Vec<TextBlob>,
@@ -338,6 +345,7 @@ impl ModulesAssets {
         manifest: ModuleManifest,
         kv_namespaces: Vec<KvNamespace>,
         r2_buckets: Vec<R2Bucket>,
+        services: Vec<ServiceBinding>,
         durable_object_classes: Vec<DurableObjectsClass>,
         migration: Option<ApiMigration>,
         text_blobs: Vec<TextBlob>,
@@ -350,6 +358,7 @@ impl ModulesAssets {
             manifest,
             kv_namespaces,
             r2_buckets,
+            services,
             durable_object_classes,
             migration,
             text_blobs,
@@ -372,6 +381,10 @@ impl ModulesAssets {
             let binding = r2.binding();
             bindings.push(binding);
         }
+        for service in &self.services {
+            let binding = service.binding();
+            bindings.push(binding);
+        }
         for class in &self.durable_object_classes {
             let binding = class.binding();
             bindings.push(binding);
@@ -1,10 +1,9 @@
 use anyhow::Result;
-use reqwest::blocking::multipart::{Form, Part};
 use serde::Serialize;
 
 use crate::settings::binding::Binding;
 
-use super::{ServiceWorkerAssets, UsageModel};
+use super::{FormPart, ServiceWorkerAssets, UsageModel};
 
 #[derive(Serialize, Debug)]
 struct Metadata {
@@ -17,45 +16,43 @@ struct Metadata {
     pub compatibility_flags: Vec<String>,
 }
 
-pub fn build_form(
+pub fn build_parts(
     assets: &ServiceWorkerAssets,
     session_config: Option<serde_json::Value>,
-) -> Result<Form> {
-    let mut form = Form::new();
+) -> Result<Vec<FormPart>> {
+    let mut parts = Vec::new();
 
     // The preview service in particular streams the request form, and requires that the
     // "metadata" part be set first, so this order is important.
-    form = add_metadata(form, assets)?;
-    form = add_files(form, assets)?;
+    add_metadata(&mut parts, assets)?;
+    add_files(&mut parts, assets)?;
     if let Some(session_config) = session_config {
-        form = add_session_config(form, session_config)?
+        add_session_config(&mut parts, session_config);
     }
 
-    log::info!("building form");
-    log::info!("{:#?}", &form);
-
-    Ok(form)
+    Ok(parts)
 }
 
-fn add_files(mut form: Form, assets: &ServiceWorkerAssets) -> Result<Form> {
-    form = form.file(assets.script_name()?, assets.script_path())?;
+fn add_files(parts: &mut Vec<FormPart>, assets: &ServiceWorkerAssets) -> Result<()> {
+    parts.push(FormPart::file(&assets.script_name()?, assets.script_path()));
 
     for wasm_module in &assets.wasm_modules {
-        form = form.file(wasm_module.filename(), wasm_module.path())?;
+        parts.push(FormPart::file(&wasm_module.filename(), wasm_module.path()));
     }
 
     for text_blob in &assets.text_blobs {
-        let part = Part::text(text_blob.data.clone())
-            .file_name(text_blob.binding.clone())
-            .mime_str("text/plain")?;
-
-        form = form.part(text_blob.binding.clone(), part);
+        parts.push(FormPart::text(
+            &text_blob.binding,
+            &text_blob.binding,
+            "text/plain",
+            text_blob.data.clone(),
+        ));
     }
 
-    Ok(form)
+    Ok(())
 }
 
-fn add_metadata(mut form: Form, assets: &ServiceWorkerAssets) -> Result<Form> {
+fn add_metadata(parts: &mut Vec<FormPart>, assets: &ServiceWorkerAssets) -> Result<()> {
     let metadata_json = serde_json::json!(&Metadata {
         body_part: assets.script_name()?,
         bindings: assets.bindings(),
@@ -64,21 +61,21 @@ fn add_metadata(mut form: Form, assets: &ServiceWorkerAssets) -> Result<Form> {
         compatibility_flags: assets.compatibility_flags.clone(),
     });
 
-    let metadata = Part::text(metadata_json.to_string())
-        .file_name("metadata.json")
-        .mime_str("application/json")?;
-
-    form = form.part("metadata", metadata);
+    parts.push(FormPart::text(
+        "metadata",
+        "metadata.json",
+        "application/json",
+        metadata_json.to_string(),
+    ));
 
-    Ok(form)
+    Ok(())
 }
 
-fn add_session_config(mut form: Form, session_config: serde_json::Value) -> Result<Form> {
-    let wrangler_session_config = Part::text(session_config.to_string())
-        .file_name("")
-        .mime_str("application/json")?;
-
-    form = form.part("wrangler-session-config", wrangler_session_config);
-
-    Ok(form)
+fn add_session_config(parts: &mut Vec<FormPart>, session_config: serde_json::Value) {
+    parts.push(FormPart::text(
+        "wrangler-session-config",
+        "",
+        "application/json",
+        session_config.to_string(),
+    ));
 }
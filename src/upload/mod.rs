@@ -8,36 +8,50 @@ pub use package::Package;
 use anyhow::Result;
 use reqwest::blocking::Client;
 
+use crate::http;
 use crate::settings::toml::Target;
 use crate::sites::AssetManifest;
+use crate::ApiError;
+use form::FormPart;
 
 pub fn script(
     client: &Client,
     target: &Target,
     asset_manifest: Option<AssetManifest>,
 ) -> Result<()> {
+    let parts = form::build_parts(target, asset_manifest, None)?;
+    script_parts(client, target, parts)
+}
+
+/// Uploads an upload form that has already been built, such as one published
+/// before.
+pub fn script_parts(client: &Client, target: &Target, parts: Vec<FormPart>) -> Result<()> {
     let worker_addr = format!(
         "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}",
         target.account_id.load()?,
         target.name,
     );
 
-    let script_upload_form = form::build(target, asset_manifest, None)?;
-
     let style = ProgressStyle::default_spinner().template("{spinner}   {msg}");
     let spinner = ProgressBar::new_spinner().with_style(style);
     spinner.set_message("Uploading script...");
     spinner.enable_steady_tick(20);
 
-    let res = client
-        .put(&worker_addr)
-        .multipart(script_upload_form)
-        .send()?;
+    // the form is built again for each attempt, since it can only be sent once
+    let res = http::send_rebuilt(client, || {
+        Ok(client
+            .put(&worker_addr)
+            .multipart(form::into_form(parts.clone())?))
+    })?;
 
     spinner.finish_and_clear();
 
     if !res.status().is_success() {
-        anyhow::bail!(error_msg(res.text()?))
+        let text = res.text()?;
+        anyhow::bail!(ApiError {
+            codes: crate::api_error_codes(&text),
+            message: error_msg(text),
+        })
     }
 
     Ok(())
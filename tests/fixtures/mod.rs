@@ -3,7 +3,9 @@
 #![allow(dead_code)]
 
 mod wrangler_toml;
-pub use wrangler_toml::{EnvConfig, KvConfig, SiteConfig, Triggers, WranglerToml, TEST_ENV_NAME};
+pub use wrangler_toml::{
+    EnvConfig, KvConfig, MigrationConfig, SiteConfig, Triggers, WranglerToml, TEST_ENV_NAME,
+};
 
 use std::fs;
 use std::fs::File;
@@ -21,6 +21,12 @@ pub struct Triggers {
     pub crons: Option<Vec<String>>,
 }
 
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct MigrationConfig {
+    pub tag: &'static str,
+    pub new_classes: Option<Vec<&'static str>>,
+}
+
 #[derive(Clone, Debug, Default, Serialize)]
 pub struct SiteConfig {
     pub bucket: Option<&'static str>,
@@ -40,12 +46,18 @@ pub struct EnvConfig {
     pub zone_id: Option<&'static str>,
     pub webpack_config: Option<&'static str>,
     pub private: Option<bool>,
+    pub usage_model: Option<&'static str>,
+    pub compatibility_date: Option<&'static str>,
+    pub compatibility_flags: Option<Vec<&'static str>>,
+    pub secrets: Option<Vec<&'static str>>,
     pub site: Option<SiteConfig>,
     #[serde(alias = "kv-namespaces")]
     pub kv_namespaces: Option<Vec<KvConfig>>,
     pub vars: Option<HashMap<&'static str, &'static str>>,
     pub text_blobs: Option<HashMap<&'static str, &'static str>>,
+    pub wasm_modules: Option<HashMap<&'static str, &'static str>>,
     pub triggers: Option<Triggers>,
+    pub migrations: Option<Vec<MigrationConfig>>,
 }
 
 impl EnvConfig {
@@ -100,13 +112,19 @@ pub struct WranglerToml {
     pub zone_id: Option<&'static str>,
     pub webpack_config: Option<&'static str>,
     pub private: Option<bool>,
+    pub usage_model: Option<&'static str>,
+    pub compatibility_date: Option<&'static str>,
+    pub compatibility_flags: Option<Vec<&'static str>>,
+    pub secrets: Option<Vec<&'static str>>,
     pub env: Option<HashMap<&'static str, EnvConfig>>,
     #[serde(alias = "kv-namespaces")]
     pub kv_namespaces: Option<Vec<KvConfig>>,
     pub site: Option<SiteConfig>,
     pub vars: Option<HashMap<&'static str, &'static str>>,
     pub text_blobs: Option<HashMap<&'static str, &'static str>>,
+    pub wasm_modules: Option<HashMap<&'static str, &'static str>>,
     pub triggers: Option<Triggers>,
+    pub migrations: Option<Vec<MigrationConfig>>,
 }
 
 impl WranglerToml {